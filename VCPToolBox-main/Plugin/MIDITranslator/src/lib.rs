@@ -15,12 +15,59 @@ const NOTE_NAMES: [&str; 12] = ["c", "c#", "d", "d#", "e", "f", "f#", "g", "g#",
 const DEFAULT_BPM: u32 = 120;
 const DEFAULT_TIME_SIG: &str = "4/4";
 const DEFAULT_TPB: u16 = 480;
+/// `[ccramp,...]` 展开插值点时的默认最小间距（以拍为单位），对应 Ardour/Evoral 的节流间隔
+const DEFAULT_MIN_CC_INTERVAL_BEATS: f64 = 1.0 / 256.0;
+/// `export_merged` 默认的小节数上限，超出的事件不再计入归并结果
+const DEFAULT_BAR_LIMIT: u32 = 1000;
+
+/// General MIDI 128 种标准音色名，下标即 Program Change 的音色号 (0-127)
+const GM_INSTRUMENT_NAMES: [&str; 128] = [
+    "Acoustic Grand Piano", "Bright Acoustic Piano", "Electric Grand Piano", "Honky-tonk Piano",
+    "Electric Piano 1", "Electric Piano 2", "Harpsichord", "Clavinet",
+    "Celesta", "Glockenspiel", "Music Box", "Vibraphone",
+    "Marimba", "Xylophone", "Tubular Bells", "Dulcimer",
+    "Drawbar Organ", "Percussive Organ", "Rock Organ", "Church Organ",
+    "Reed Organ", "Accordion", "Harmonica", "Tango Accordion",
+    "Acoustic Guitar (nylon)", "Acoustic Guitar (steel)", "Electric Guitar (jazz)", "Electric Guitar (clean)",
+    "Electric Guitar (muted)", "Overdriven Guitar", "Distortion Guitar", "Guitar Harmonics",
+    "Acoustic Bass", "Electric Bass (finger)", "Electric Bass (pick)", "Fretless Bass",
+    "Slap Bass 1", "Slap Bass 2", "Synth Bass 1", "Synth Bass 2",
+    "Violin", "Viola", "Cello", "Contrabass",
+    "Tremolo Strings", "Pizzicato Strings", "Orchestral Harp", "Timpani",
+    "String Ensemble 1", "String Ensemble 2", "Synth Strings 1", "Synth Strings 2",
+    "Choir Aahs", "Voice Oohs", "Synth Voice", "Orchestra Hit",
+    "Trumpet", "Trombone", "Tuba", "Muted Trumpet",
+    "French Horn", "Brass Section", "Synth Brass 1", "Synth Brass 2",
+    "Soprano Sax", "Alto Sax", "Tenor Sax", "Baritone Sax",
+    "Oboe", "English Horn", "Bassoon", "Clarinet",
+    "Piccolo", "Flute", "Recorder", "Pan Flute",
+    "Blown Bottle", "Shakuhachi", "Whistle", "Ocarina",
+    "Lead 1 (square)", "Lead 2 (sawtooth)", "Lead 3 (calliope)", "Lead 4 (chiff)",
+    "Lead 5 (charang)", "Lead 6 (voice)", "Lead 7 (fifths)", "Lead 8 (bass + lead)",
+    "Pad 1 (new age)", "Pad 2 (warm)", "Pad 3 (polysynth)", "Pad 4 (choir)",
+    "Pad 5 (bowed)", "Pad 6 (metallic)", "Pad 7 (halo)", "Pad 8 (sweep)",
+    "FX 1 (rain)", "FX 2 (soundtrack)", "FX 3 (crystal)", "FX 4 (atmosphere)",
+    "FX 5 (brightness)", "FX 6 (goblins)", "FX 7 (echoes)", "FX 8 (sci-fi)",
+    "Sitar", "Banjo", "Shamisen", "Koto",
+    "Kalimba", "Bag pipe", "Fiddle", "Shanai",
+    "Tinkle Bell", "Agogo", "Steel Drums", "Woodblock",
+    "Taiko Drum", "Melodic Tom", "Synth Drum", "Reverse Cymbal",
+    "Guitar Fret Noise", "Breath Noise", "Seashore", "Bird Tweet",
+    "Telephone Ring", "Helicopter", "Applause", "Gunshot",
+];
 
 // MIDI常量
 const MIDI_HEADER: [u8; 4] = [b'M', b'T', b'h', b'd'];
 const MIDI_TRACK_HEADER: [u8; 4] = [b'M', b'T', b'r', b'k'];
 const END_OF_TRACK: [u8; 4] = [0x00, 0xFF, 0x2F, 0x00];
 
+/// 设备初始化 SysEx：通用 MIDI 开启 (不含 0xF0/0xF7 外壳)
+const GM_ON_SYSEX: [u8; 4] = [0x7E, 0x7F, 0x09, 0x01];
+/// 设备初始化 SysEx：Roland GS 开启
+const GS_ON_SYSEX: [u8; 9] = [0x41, 0x10, 0x42, 0x12, 0x40, 0x00, 0x7F, 0x00, 0x41];
+/// 设备初始化 SysEx：Yamaha XG 开启
+const XG_ON_SYSEX: [u8; 7] = [0x43, 0x10, 0x4C, 0x00, 0x00, 0x7E, 0x00];
+
 // ==================== 核心数据结构 ====================
 
 /// 自主设计的MIDI事件类型 - 只包含对AI音乐创作重要的类型
@@ -37,7 +84,13 @@ enum MidiEventType {
     // 触感表达
     Aftertouch { channel: u8, note: u8, pressure: u8 },
     ChannelAftertouch { channel: u8, pressure: u8 },
-    
+
+    // 乐器选择 (General MIDI 音色号 0-127)
+    ProgramChange { channel: u8, program: u8 },
+
+    // 系统专有消息 (设备初始化、厂商自定义指令等)
+    SysEx(Vec<u8>),
+
     // 文本元信息
     Text(String),
     Lyric(String),    // 特殊标记：触发人声/旋律分离检查
@@ -63,15 +116,17 @@ struct MidiFile {
     format: u16,                    // 0,1,2
     ticks_per_quarter: u16,        // 时间精度
     tracks: Vec<Vec<TrackEvent>>,  // 轨道数据
+    use_running_status: bool,     // 是否启用 running status 压缩（默认关闭，保持字节级可逆）
 }
 
 impl MidiFile {
     /// 创建新的MIDI文件
-    fn new(format: u16, ticks_per_quarter: u16) -> Self {
+    fn new(format: u16, ticks_per_quarter: u16, use_running_status: bool) -> Self {
         Self {
             format,
             ticks_per_quarter,
             tracks: Vec::new(),
+            use_running_status,
         }
     }
     
@@ -124,13 +179,15 @@ impl MidiFile {
         
         // 先写入轨道数据到临时缓冲区
         let mut track_data = Vec::new();
+        // running status：同一状态字节的连续 channel voice 事件可省略状态字节
+        let mut running_status: Option<u8> = None;
         // 按顺序写入事件
         for event in track {
             // 写入delta time（变长编码）
             self.write_variable_length(&mut track_data, event.delta_time);
-            
+
             // 写入事件数据
-            self.write_event_data(&mut track_data, &event.event_type);
+            self.write_event_data(&mut track_data, &event.event_type, &mut running_status);
         }
         
         // 写入轨道结束标志
@@ -143,41 +200,65 @@ impl MidiFile {
         bytes.extend(track_data);
     }
     
-    /// 写入事件数据
-    fn write_event_data(&self, bytes: &mut Vec<u8>, event_type: &MidiEventType) {
-        match event_type {
+    /// 写入事件数据。`running_status` 记录上一个写出的 channel voice 状态字节；
+    /// 当 `use_running_status` 开启且状态字节与上一条相同时，省略状态字节。
+    /// 任何 meta/sysex 事件（0xF0-0xFF）都会把 running status 重置为 None。
+    fn write_event_data(
+        &self,
+        bytes: &mut Vec<u8>,
+        event_type: &MidiEventType,
+        running_status: &mut Option<u8>,
+    ) {
+        // 先尝试把当前事件当作 channel voice 事件处理，取得其状态字节和数据字节
+        let channel_voice = match event_type {
             MidiEventType::NoteOn { channel, note, velocity } => {
-                bytes.push(0x90 | (channel & 0x0F));
-                bytes.push(*note);
-                bytes.push(*velocity);
+                Some((0x90 | (channel & 0x0F), vec![*note, *velocity]))
             }
             MidiEventType::NoteOff { channel, note } => {
-                bytes.push(0x80 | (channel & 0x0F));
-                bytes.push(*note);
-                bytes.push(0x00); // NoteOff速度通常为0
+                Some((0x80 | (channel & 0x0F), vec![*note, 0x00])) // NoteOff速度通常为0
             }
             MidiEventType::Controller { channel, controller, value } => {
-                bytes.push(0xB0 | (channel & 0x0F));
-                bytes.push(*controller);
-                bytes.push(*value);
+                Some((0xB0 | (channel & 0x0F), vec![*controller, *value]))
             }
             MidiEventType::PitchBend { channel, value } => {
                 let adjusted = ((*value as i32 + 8192) & 0x3FFF) as u16;
                 let lsb = (adjusted & 0x7F) as u8;
                 let msb = ((adjusted >> 7) & 0x7F) as u8;
-                
-                bytes.push(0xE0 | (channel & 0x0F));
-                bytes.push(lsb);
-                bytes.push(msb);
+                Some((0xE0 | (channel & 0x0F), vec![lsb, msb]))
             }
             MidiEventType::Aftertouch { channel, note, pressure } => {
-                bytes.push(0xA0 | (channel & 0x0F));
-                bytes.push(*note);
-                bytes.push(*pressure);
+                Some((0xA0 | (channel & 0x0F), vec![*note, *pressure]))
             }
             MidiEventType::ChannelAftertouch { channel, pressure } => {
-                bytes.push(0xD0 | (channel & 0x0F));
-                bytes.push(*pressure);
+                Some((0xD0 | (channel & 0x0F), vec![*pressure]))
+            }
+            MidiEventType::ProgramChange { channel, program } => {
+                Some((0xC0 | (channel & 0x0F), vec![*program]))
+            }
+            _ => None,
+        };
+
+        if let Some((status, data)) = channel_voice {
+            if self.use_running_status && *running_status == Some(status) {
+                // 状态字节与上一条相同，省略之
+            } else {
+                bytes.push(status);
+                *running_status = Some(status);
+            }
+            bytes.extend_from_slice(&data);
+            return;
+        }
+
+        // 非 channel voice 事件（meta/sysex）一律重置 running status
+        *running_status = None;
+
+        match event_type {
+            MidiEventType::SysEx(payload) => {
+                bytes.push(0xF0);
+                // 长度涵盖 payload 本身加上结尾的 0xF7 终止符
+                self.write_variable_length(bytes, payload.len() as u32 + 1);
+                bytes.extend_from_slice(payload);
+                bytes.push(0xF7);
             }
             MidiEventType::Text(text) => {
                 bytes.push(0xFF); // Meta event
@@ -202,11 +283,11 @@ impl MidiFile {
                 bytes.push(((tempo >> 8) & 0xFF) as u8);
                 bytes.push((tempo & 0xFF) as u8);
             }
-            MidiEventType::TimeSignature { 
-                numerator, 
-                denominator, 
-                clocks_per_click, 
-                thirty_seconds_per_quarter 
+            MidiEventType::TimeSignature {
+                numerator,
+                denominator,
+                clocks_per_click,
+                thirty_seconds_per_quarter
             } => {
                 bytes.push(0xFF); // Meta event
                 bytes.push(0x58); // Time signature event
@@ -221,6 +302,7 @@ impl MidiFile {
                 bytes.push(0x03); // Track name event
                 self.write_meta_text(bytes, name);
             }
+            _ => unreachable!("channel voice 事件已在上面提前返回"),
         }
     }
     
@@ -248,6 +330,133 @@ impl MidiFile {
     }
 }
 
+// ==================== 小节寻址 ====================
+
+/// 拍号区间：从 `start_tick`（对应小节 `start_bar`，1起）开始，直到下一次拍号变更为止，
+/// 本区间内每拍的 tick 数固定为 `ticks_per_beat`。
+struct BarRegion {
+    start_tick: u32,
+    start_bar: u32,
+    numerator: u8,
+    ticks_per_beat: f64,
+}
+
+/// 小节:拍:tick 定位表：依据有序的拍号变更，在绝对 tick 和 (小节,拍,tick) 之间互转。
+/// 假设拍号变更总是落在小节线上，这也是真实 SMF 文件的常规写法。
+struct BarTimeMap {
+    regions: Vec<BarRegion>,
+    ticks_per_quarter: f64,
+}
+
+impl BarTimeMap {
+    /// `time_sigs` 为 (起始tick, 分子, 分母) 列表，顺序不要求有序；若不是从 tick 0 开始，
+    /// 会在最前面补一个默认 4/4 区间。
+    fn from_time_signatures(ticks_per_quarter: f64, time_sigs: &[(u32, u8, u8)]) -> Self {
+        let mut sigs: Vec<(u32, u8, u8)> = time_sigs.to_vec();
+        sigs.sort_by_key(|(tick, _, _)| *tick);
+        if sigs.first().map(|(tick, _, _)| *tick) != Some(0) {
+            sigs.insert(0, (0, 4, 4));
+        }
+
+        let mut regions = Vec::with_capacity(sigs.len());
+        let mut start_bar = 1u32;
+        let mut prev: Option<(u32, f64, u8)> = None; // (区间起始tick, 每拍tick数, 分子)
+
+        for (start_tick, numerator, denominator) in sigs {
+            if let Some((prev_start, prev_ticks_per_beat, prev_numerator)) = prev {
+                let ticks_per_measure = prev_ticks_per_beat * prev_numerator as f64;
+                let elapsed = (start_tick - prev_start) as f64;
+                start_bar += (elapsed / ticks_per_measure).round() as u32;
+            }
+            let ticks_per_beat = ticks_per_quarter * 4.0 / denominator as f64;
+            regions.push(BarRegion { start_tick, start_bar, numerator, ticks_per_beat });
+            prev = Some((start_tick, ticks_per_beat, numerator));
+        }
+
+        Self { regions, ticks_per_quarter }
+    }
+
+    fn region_for_tick(&self, tick: u32) -> &BarRegion {
+        self.regions.iter().rev().find(|r| r.start_tick <= tick).unwrap_or(&self.regions[0])
+    }
+
+    fn region_for_bar(&self, bar: u32) -> &BarRegion {
+        self.regions.iter().rev().find(|r| r.start_bar <= bar).unwrap_or(&self.regions[0])
+    }
+
+    /// 绝对 tick -> (小节[1起], 拍[1起], 拍内 tick 偏移)
+    fn tick_to_bar_beat_tick(&self, tick: u32) -> (u32, u32, u32) {
+        let region = self.region_for_tick(tick);
+        let ticks_per_measure = region.ticks_per_beat * region.numerator as f64;
+        let elapsed = (tick - region.start_tick) as f64;
+        let measures = (elapsed / ticks_per_measure).floor();
+        let within_measure = elapsed - measures * ticks_per_measure;
+        let beat = (within_measure / region.ticks_per_beat).floor();
+        let tick_in_beat = within_measure - beat * region.ticks_per_beat;
+        (region.start_bar + measures as u32, beat as u32 + 1, tick_in_beat.round() as u32)
+    }
+
+    /// (小节[1起], 拍[1起], 拍内 tick 偏移) -> 绝对 tick
+    fn bar_beat_tick_to_tick(&self, bar: u32, beat: u32, tick: u32) -> u32 {
+        let region = self.region_for_bar(bar);
+        let ticks_per_measure = region.ticks_per_beat * region.numerator as f64;
+        let measures_elapsed = (bar - region.start_bar) as f64;
+        let offset = measures_elapsed * ticks_per_measure
+            + beat.saturating_sub(1) as f64 * region.ticks_per_beat
+            + tick as f64;
+        region.start_tick + offset.round() as u32
+    }
+
+    /// 格式化为 "小节:拍:tick" 字符串
+    fn format_bar_beat_tick(&self, tick: u32) -> String {
+        let (bar, beat, tick_in_beat) = self.tick_to_bar_beat_tick(tick);
+        format!("{}:{}:{}", bar, beat, tick_in_beat)
+    }
+}
+
+/// "小节:拍:tick"（小节、拍均 1 起，tick 是拍内偏移）→ 绝对拍数（以四分音符为单位，和其余
+/// DSL 事件的拍号字段同单位），依据 `time_sigs`（`BarTimeMap::from_time_signatures` 同款
+/// (起始tick, 分子, 分母) 列表）。"拍"指所在拍号区间的分母音符（如 6/8 里一拍是八分音符）：
+/// 结果 = 之前各小节长度之和（每小节 `numerator * 4 / denominator` 个四分音符）
+/// + `(beat-1) * 4 / denominator` + `tick / ticks_per_quarter`。
+/// 小节/拍为 0，或 tick 越界（>= 该拍的 tick 数）时返回 `None`。
+fn mbt_to_beat(bar: u32, beat: u32, tick: u32, ticks_per_quarter: f64, time_sigs: &[(u32, u8, u8)]) -> Option<f64> {
+    if bar == 0 || beat == 0 {
+        return None;
+    }
+    let bar_map = BarTimeMap::from_time_signatures(ticks_per_quarter, time_sigs);
+    let region = bar_map.region_for_bar(bar);
+    if tick as f64 >= region.ticks_per_beat {
+        return None;
+    }
+    let abs_tick = bar_map.bar_beat_tick_to_tick(bar, beat, tick);
+    Some(abs_tick as f64 / ticks_per_quarter)
+}
+
+// ==================== 网格量化 ====================
+
+/// 网格量化参数：`grid_step` 为网格步长（单位 tick，由 `ticks_per_quarter * 4 / grid_denominator` 算得），
+/// `strength` 为吸附强度（0.0 不吸附，1.0 完全吸附到网格线），`swing` 为摇摆比例
+/// （每个奇数网格位的目标线延后 `swing * grid_step`）。
+struct GridQuantizeConfig {
+    grid_step: f64,
+    strength: f64,
+    swing: f64,
+}
+
+impl GridQuantizeConfig {
+    /// 把一个绝对 tick 吸附到网格：`snapped = original + strength * (grid_nearest - original)`
+    fn snap(&self, tick: u32) -> u32 {
+        let grid_index = (tick as f64 / self.grid_step).round();
+        let mut grid_tick = grid_index * self.grid_step;
+        if (grid_index as i64).rem_euclid(2) != 0 {
+            grid_tick += self.swing * self.grid_step;
+        }
+        let snapped = tick as f64 + self.strength * (grid_tick - tick as f64);
+        snapped.round().max(0.0) as u32
+    }
+}
+
 // ==================== 主结构体 ====================
 
 #[napi]
@@ -263,16 +472,55 @@ impl MidiQuantizer {
     }
 
     /// 核心API1：MIDI -> DSL（无损转换）
+    /// `use_bar_addressing` 可选，开启后位置用"小节:拍:tick"代替绝对拍数浮点值（默认关闭）
     #[napi]
-    pub fn quantize(&self, midi_data: Buffer) -> Result<String> {
-        panic::catch_unwind(|| self.quantize_internal(&midi_data))
+    pub fn quantize(&self, midi_data: Buffer, use_bar_addressing: Option<bool>) -> Result<String> {
+        let use_bar_addressing = use_bar_addressing.unwrap_or(false);
+        panic::catch_unwind(|| self.quantize_internal(&midi_data, use_bar_addressing))
+            .map_err(|e| Error::new(Status::GenericFailure, format!("Rust panic: {:?}", e)))?
+    }
+
+    /// 辅助API：MIDI -> DSL（网格量化版）
+    /// 把每个音符的起止 tick 向最近的网格线吸附，用于清理人声演奏录入的时值误差。
+    /// `grid_denominator` 为网格分母（如 16 代表十六分音符网格，网格步长 = TPB*4/grid_denominator）；
+    /// `strength` 为吸附强度 0.0-1.0；`swing` 可选，为摇摆比例，延后每个奇数网格位（默认 0.0）。
+    /// 时长被吸附后会被钳制为至少一个网格步长；tempo/拍号事件不受影响。
+    #[napi]
+    pub fn quantize_with_grid(
+        &self,
+        midi_data: Buffer,
+        grid_denominator: u32,
+        strength: f64,
+        swing: Option<f64>,
+    ) -> Result<String> {
+        let swing = swing.unwrap_or(0.0);
+        panic::catch_unwind(|| self.quantize_with_grid_internal(&midi_data, grid_denominator, strength, swing))
             .map_err(|e| Error::new(Status::GenericFailure, format!("Rust panic: {:?}", e)))?
     }
 
     /// 核心API2：DSL -> MIDI（包含验证）
+    /// `reset_mode` 可选，用于在指挥轨道开头插入设备初始化 SysEx："gm" / "gs" / "xg"
+    /// `use_running_status` 可选，开启后对连续同状态的 channel voice 事件使用 running status 压缩（默认关闭，保持字节级可逆）
+    /// `use_bar_addressing` 已保留用于兼容旧调用方；"小节:拍:tick"格式现在始终可以在任何
+    /// 事件位置出现（依据 Timeline 里的拍号变更换算为绝对拍数），不再需要显式开启
+    /// `min_cc_interval_beats` 可选，控制 `[ccramp,...]` 展开插值点之间的最小间距（以拍为单位），
+    /// 默认 `DEFAULT_MIN_CC_INTERVAL_BEATS`（1/256 拍）
     #[napi]
-    pub fn generate(&self, dsl: String) -> Result<Buffer> {
-        self.generate_internal(&dsl)
+    pub fn generate(
+        &self,
+        dsl: String,
+        reset_mode: Option<String>,
+        use_running_status: Option<bool>,
+        use_bar_addressing: Option<bool>,
+        min_cc_interval_beats: Option<f64>,
+    ) -> Result<Buffer> {
+        self.generate_internal(
+            &dsl,
+            reset_mode.as_deref(),
+            use_running_status.unwrap_or(false),
+            use_bar_addressing.unwrap_or(false),
+            min_cc_interval_beats.unwrap_or(DEFAULT_MIN_CC_INTERVAL_BEATS),
+        )
     }
 
     /// 核心API3：DSL语法验证
@@ -302,13 +550,342 @@ impl MidiQuantizer {
         
         Ok(events)
     }
+
+    /// 辅助API：人声/旋律分离
+    /// 按既有规则（歌词轨道禁止包含音符事件），把歌词轨道里的歌词事件和其余轨道里的全部音符事件
+    /// 按拍对齐：每条歌词取拍位最近、且该拍位同时发声音符里音高最高的一个，组成单声部人声旋律轨；
+    /// 其余音符原样留在伴奏轨。返回 `[Track0("Vocal"): ..., Track1("Accompaniment"): ..., 对齐条目...]`，
+    /// 两条轨道都是标准的 `TrackN("name"): ` 格式，可以直接喂给 `validate_dsl`/`generate` 等
+    /// 其余 DSL 工具链；每条对齐条目格式为 `"拍|歌词|音名"`。
+    #[napi]
+    pub fn separate_vocal_melody(&self, dsl: String) -> Result<Vec<String>> {
+        Ok(self.separate_vocal_melody_internal(&dsl))
+    }
+
+    /// 辅助API：移调
+    /// 把 DSL 里每个音符事件（NoteOn/NoteOff；`key_aware` 时还包括 Aftertouch 音名）的音高
+    /// 整体平移 `semitones` 个半音：`name_to_midi` 换算出原始音高，加上偏移量后若落在 0-127
+    /// 之外就整条事件丢弃（钳制到边界会变成完全不同的音，没有意义），否则用 `midi_to_name`
+    /// 换算回音名重新写入。NoteOn 和 NoteOff 共享同一个音名，换算结果必然一致，配对关系不会被破坏。
+    /// 结果会用 `validate_dsl_internal` 复核，如引入了新的验证错误会一并返回。
+    #[napi]
+    pub fn transpose(&self, dsl: String, semitones: i32, key_aware: Option<bool>) -> Result<String> {
+        let result = self.transpose_internal(&dsl, semitones, key_aware.unwrap_or(false));
+        let errors = self.validate_dsl_internal(&result);
+        if !errors.is_empty() {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("移调后 DSL 验证失败: {}", errors.join("; ")),
+            ));
+        }
+        Ok(result)
+    }
+
+    /// 辅助API：按声道拆分轨道
+    /// 把一条多声道轨道拆成每个 `chN` 各自独立的 `TrackK:` 行，每条声道内部保持原有事件出现顺序；
+    /// 没有声道归属的事件（Text/Lyric/Marker/SysEx）归入拆出来的第一条轨道。Timeline 原样保留。
+    /// 对应经典 midilib 教程里的 split/transpose 示例。结果同样用 `validate_dsl_internal` 复核。
+    #[napi]
+    pub fn split_by_channel(&self, dsl: String) -> Result<String> {
+        let result = self.split_by_channel_internal(&dsl);
+        let errors = self.validate_dsl_internal(&result);
+        if !errors.is_empty() {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("按声道拆分后 DSL 验证失败: {}", errors.join("; ")),
+            ));
+        }
+        Ok(result)
+    }
+
+    /// 辅助API：多轨归并导出
+    /// 把 DSL 里所有 `TrackN:` 轨道连同 Timeline 的 tempo/拍号事件一起归并成一条按绝对 tick
+    /// 排序的 Format-0 单轨事件流，给出统一的整体回放视角（按声部 peekable 归并，类比
+    /// Polyrhythmix 的实现），回放时仍保留原有的速度/拍号变化。
+    /// `bar_limit` 可选（默认 1000 小节），依据 Timeline 的拍号变更换算成 tick 数作为硬上限，
+    /// 超出的事件直接丢弃。
+    #[napi]
+    pub fn export_merged(&self, dsl: String, bar_limit: Option<u32>) -> Result<Buffer> {
+        panic::catch_unwind(|| self.export_merged_internal(&dsl, bar_limit.unwrap_or(DEFAULT_BAR_LIMIT)))
+            .map_err(|e| Error::new(Status::GenericFailure, format!("Rust panic: {:?}", e)))?
+    }
 }
 
 // ==================== 内部实现 ====================
 
+/// `expand_cc_ramp` 的参数包：把 `[ccramp,...]` 展开成插值点所需的全部字段打包成一个结构体，
+/// 避免随 `min_interval_beats` 这类新增可配置项继续堆叠独立参数（类比 `GridQuantizeConfig`）
+struct CcRampConfig {
+    start_beat: f64,
+    end_beat: f64,
+    controller: u8,
+    start_val: u8,
+    end_val: u8,
+    channel: u8,
+    /// 插值点之间的最小间距（以拍为单位），默认 `DEFAULT_MIN_CC_INTERVAL_BEATS`
+    min_interval_beats: f64,
+}
+
 impl MidiQuantizer {
     /// 量化内部实现：MIDI二进制 -> DSL字符串
-    fn quantize_internal(&self, midi_data: &[u8]) -> Result<String> {
+    fn quantize_internal(&self, midi_data: &[u8], use_bar_addressing: bool) -> Result<String> {
+        self.quantize_internal_ex(midi_data, use_bar_addressing, None)
+    }
+
+    /// 网格量化内部实现：MIDI二进制 -> DSL字符串，音符起止 tick 吸附到网格
+    fn quantize_with_grid_internal(
+        &self,
+        midi_data: &[u8],
+        grid_denominator: u32,
+        strength: f64,
+        swing: f64,
+    ) -> Result<String> {
+        if grid_denominator == 0 {
+            return Err(Error::new(Status::InvalidArg, "grid_denominator 不能为 0".to_string()));
+        }
+        // ticks_per_quarter 需要先解析一次 header 才能算出网格步长，其余逻辑与 quantize_internal_ex 共用
+        let smf = Smf::parse(midi_data)
+            .map_err(|e| Error::new(Status::InvalidArg, format!("MIDI 解析失败: {:?}", e)))?;
+        let ticks_per_beat = match smf.header.timing {
+            midly::Timing::Metrical(tp) => tp.as_int() as f64,
+            midly::Timing::Timecode(fps, subframes) => {
+                let fps_val = fps.as_f32();
+                let subframes_val = subframes as f32;
+                ((fps_val * subframes_val) / 4.0) as f64
+            }
+        };
+        let grid_step = ticks_per_beat * 4.0 / grid_denominator as f64;
+        let grid = GridQuantizeConfig { grid_step, strength, swing };
+
+        self.quantize_internal_ex(midi_data, false, Some(&grid))
+    }
+
+    /// 人声/旋律分离内部实现：详见 `separate_vocal_melody` 文档
+    fn separate_vocal_melody_internal(&self, dsl: &str) -> Vec<String> {
+        let lyric_re = regex::Regex::new(r#"\[lyric,(\d+\.\d+),"([^"]+)"\]"#).unwrap();
+        let note_on_re = regex::Regex::new(r"\[([a-z]#?\d+),(\d+\.\d+),(\d+),ch(\d+)\]").unwrap();
+        let note_off_re = regex::Regex::new(r"\[~([a-z]#?\d+),(\d+\.\d+),ch(\d+)\]").unwrap();
+
+        // 1. 收集所有歌词事件（按拍排序）
+        let mut lyrics: Vec<(f64, String)> = Vec::new();
+        for line in dsl.lines() {
+            if line.starts_with("Track") {
+                for cap in lyric_re.captures_iter(line) {
+                    lyrics.push((cap[1].parse().unwrap_or(0.0), cap[2].to_string()));
+                }
+            }
+        }
+        lyrics.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        // 2. 收集非歌词轨道里全部音符相关 token（原样保留，供伴奏轨道复用）及 NoteOn 的 (拍,音高,声道)
+        let mut note_ons: Vec<(f64, u8, u8, String)> = Vec::new();
+        let mut all_tokens: Vec<String> = Vec::new();
+        for line in dsl.lines() {
+            if !line.starts_with("Track") || lyric_re.is_match(line) {
+                continue; // 既有规则：歌词轨道禁止包含音符事件，此处不再重复校验
+            }
+            for token in line.split_whitespace().skip(1) {
+                all_tokens.push(token.to_string());
+                if let Some(cap) = note_on_re.captures(token) {
+                    let beat: f64 = cap[2].parse().unwrap_or(0.0);
+                    let pitch = name_to_midi(&cap[1]);
+                    let channel: u8 = cap[4].parse().unwrap_or(0);
+                    note_ons.push((beat, pitch, channel, token.to_string()));
+                }
+            }
+        }
+
+        // 3. 每条歌词挑拍位最近、同拍位里音高最高且尚未占用的 NoteOn，组成人声旋律轨
+        let mut vocal_tokens: Vec<String> = Vec::new();
+        let mut alignment: Vec<String> = Vec::new();
+        let mut used: std::collections::HashSet<usize> = std::collections::HashSet::new();
+
+        for (lyric_beat, syllable) in &lyrics {
+            let nearest_beat = note_ons.iter().enumerate()
+                .filter(|(i, _)| !used.contains(i))
+                .map(|(_, (b, _, _, _))| *b)
+                .min_by(|a, b| (a - lyric_beat).abs().partial_cmp(&(b - lyric_beat).abs()).unwrap());
+
+            let nearest_beat = match nearest_beat {
+                Some(b) => b,
+                None => continue,
+            };
+
+            let picked = note_ons.iter().enumerate()
+                .filter(|(i, (b, _, _, _))| !used.contains(i) && *b == nearest_beat)
+                .max_by_key(|(_, (_, pitch, _, _))| *pitch)
+                .map(|(i, e)| (i, e.clone()));
+
+            if let Some((idx, (beat, pitch, channel, token))) = picked {
+                used.insert(idx);
+                vocal_tokens.push(token.clone());
+
+                // 若存在对应的 NoteOff，一并带入人声轨道，保持可回放
+                let note_name = midi_to_name(pitch);
+                if let Some(off_token) = all_tokens.iter().find(|t| {
+                    note_off_re.captures(t).map_or(false, |c| {
+                        &c[1] == note_name.as_str()
+                            && c[3].parse::<u8>().unwrap_or(u8::MAX) == channel
+                            && c[2].parse::<f64>().unwrap_or(0.0) > beat
+                    })
+                }) {
+                    vocal_tokens.push(off_token.clone());
+                }
+
+                alignment.push(format!("{}|{}|{}", beat, syllable, note_name));
+            }
+        }
+
+        // 4. 伴奏轨道 = 所有未被选入人声轨道的 token，保持原有出现顺序
+        let accompaniment_tokens: Vec<String> = all_tokens.into_iter()
+            .filter(|t| !vocal_tokens.contains(t))
+            .collect();
+
+        let mut result = vec![
+            format!("Track0(\"Vocal\"): {}", vocal_tokens.join(" ")),
+            format!("Track1(\"Accompaniment\"): {}", accompaniment_tokens.join(" ")),
+        ];
+        result.extend(alignment);
+        result
+    }
+
+    /// 把 NoteOn/NoteOff/Aftertouch 里的一个音名按 `semitones` 移调，越界（<0 或 >127）返回 `None`
+    fn transpose_note_name(name: &str, semitones: i32) -> Option<String> {
+        let pitch = name_to_midi(name) as i32 + semitones;
+        if pitch < 0 || pitch > 127 {
+            None
+        } else {
+            Some(midi_to_name(pitch as u8))
+        }
+    }
+
+    /// 移调内部实现：逐行处理轨道内容，NoteOn（逗号/冒号两种符号时值写法）、NoteOff
+    /// 各自独立匹配整条事件；`key_aware` 时一并处理 Aftertouch 的音名字段。越界的事件
+    /// 整条丢弃后，用一次 "多个连续空格->单个空格" 的清理去掉留下的空位；该清理作用于整行，
+    /// 引号内文本里的连续空格同样会被压缩成一个。
+    fn transpose_internal(&self, dsl: &str, semitones: i32, key_aware: bool) -> String {
+        let note_on_comma_re =
+            regex::Regex::new(r#"\[([a-z]#?\d+),(\d+\.\d+),(\d+),ch(\d+)(,[whqestx](?:\.|t)?)?\]"#).unwrap();
+        let note_on_colon_re =
+            regex::Regex::new(r#"\[([a-z]#?\d+),(\d+\.\d+),(\d+),ch(\d+):([whqestx]\.?(?:/\d+)?)\]"#).unwrap();
+        let note_off_re = regex::Regex::new(r"\[~([a-z]#?\d+),(\d+\.\d+),ch(\d+)\]").unwrap();
+        let at_re = regex::Regex::new(r"\[at,(\d+\.\d+),(\d+),ch(\d+),([a-z]#?\d+)\]").unwrap();
+        let extra_spaces_re = regex::Regex::new(r" {2,}").unwrap();
+
+        dsl.lines()
+            .map(|line| {
+                if !line.starts_with("Track") {
+                    return line.to_string();
+                }
+
+                let mut line = note_on_comma_re
+                    .replace_all(line, |cap: &regex::Captures| {
+                        match Self::transpose_note_name(&cap[1], semitones) {
+                            Some(name) => format!(
+                                "[{},{},{},ch{}{}]",
+                                name, &cap[2], &cap[3], &cap[4],
+                                cap.get(5).map_or("", |m| m.as_str())
+                            ),
+                            None => String::new(),
+                        }
+                    })
+                    .into_owned();
+
+                line = note_on_colon_re
+                    .replace_all(&line, |cap: &regex::Captures| {
+                        match Self::transpose_note_name(&cap[1], semitones) {
+                            Some(name) => format!("[{},{},{},ch{}:{}]", name, &cap[2], &cap[3], &cap[4], &cap[5]),
+                            None => String::new(),
+                        }
+                    })
+                    .into_owned();
+
+                line = note_off_re
+                    .replace_all(&line, |cap: &regex::Captures| {
+                        match Self::transpose_note_name(&cap[1], semitones) {
+                            Some(name) => format!("[~{},{},ch{}]", name, &cap[2], &cap[3]),
+                            None => String::new(),
+                        }
+                    })
+                    .into_owned();
+
+                if key_aware {
+                    line = at_re
+                        .replace_all(&line, |cap: &regex::Captures| {
+                            match Self::transpose_note_name(&cap[4], semitones) {
+                                Some(name) => format!("[at,{},{},ch{},{}]", &cap[1], &cap[2], &cap[3], name),
+                                None => String::new(),
+                            }
+                        })
+                        .into_owned();
+                }
+
+                extra_spaces_re.replace_all(line.trim_end(), " ").into_owned()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// 按声道拆分内部实现：把每条轨道行按 token 里出现的 `chN` 分桶，每个声道各自独立
+    /// 成一条新的 `TrackK:` 行（K 跨所有原轨道顺序编号）；同一声道内部的事件维持原有出现顺序。
+    /// 没有声道归属的 token（Text/Lyric/Marker/SysEx）归入该轨道拆出来的第一条声道轨道，
+    /// 避免事件丢失。Timeline 及其它非 Track 行原样保留。
+    fn split_by_channel_internal(&self, dsl: &str) -> String {
+        let ch_re = regex::Regex::new(r"ch(\d+)").unwrap();
+        let track_re = regex::Regex::new(r#"Track(\d+)(?:\("[^"]*"\))?: (.+)"#).unwrap();
+
+        let mut out_lines: Vec<String> = Vec::new();
+        let mut next_track_idx = 0u32;
+
+        for line in dsl.lines() {
+            let Some(cap) = track_re.captures(line) else {
+                out_lines.push(line.to_string());
+                continue;
+            };
+            let content = &cap[2];
+
+            let entries: Vec<(Option<u8>, &str)> = content
+                .split_whitespace()
+                .map(|token| (ch_re.captures(token).and_then(|c| c[1].parse().ok()), token))
+                .collect();
+
+            let mut channel_order: Vec<u8> = Vec::new();
+            for (ch, _) in &entries {
+                if let Some(c) = ch {
+                    if !channel_order.contains(c) {
+                        channel_order.push(*c);
+                    }
+                }
+            }
+
+            if channel_order.is_empty() {
+                out_lines.push(format!("Track{}: {}", next_track_idx, content));
+                next_track_idx += 1;
+                continue;
+            }
+
+            for (i, ch) in channel_order.iter().enumerate() {
+                let tokens: Vec<&str> = entries
+                    .iter()
+                    .filter(|(c, _)| *c == Some(*ch) || (i == 0 && c.is_none()))
+                    .map(|(_, t)| *t)
+                    .collect();
+                out_lines.push(format!("Track{}: {}", next_track_idx, tokens.join(" ")));
+                next_track_idx += 1;
+            }
+        }
+
+        out_lines.join("\n")
+    }
+
+    /// 量化内部实现的共用核心：MIDI二进制 -> DSL字符串。
+    /// `grid` 传入时对每个音符的起止 tick 做网格吸附；tempo/拍号等元事件不受影响。
+    fn quantize_internal_ex(
+        &self,
+        midi_data: &[u8],
+        use_bar_addressing: bool,
+        grid: Option<&GridQuantizeConfig>,
+    ) -> Result<String> {
         // 使用midly作为可靠的解析器
         let smf = Smf::parse(midi_data)
             .map_err(|e| Error::new(Status::InvalidArg, format!("MIDI 解析失败: {:?}", e)))?;
@@ -349,11 +926,28 @@ impl MidiQuantizer {
         // 3. 构建时间线DSL
         let timeline_dsl = self.build_timeline_dsl(&smf, ticks_per_beat, &timeline_events);
 
+        // 3.5 如需小节寻址，依据已解析的拍号变更构建定位表
+        let bar_map = if use_bar_addressing {
+            let time_sigs: Vec<(u32, u8, u8)> = timeline_events
+                .iter()
+                .filter(|(_, typ, _)| *typ == "tsig")
+                .map(|(tick, _, value)| {
+                    let parts: Vec<&str> = value.split('/').collect();
+                    let numerator = parts.first().and_then(|s| s.parse().ok()).unwrap_or(4);
+                    let denominator = parts.get(1).and_then(|s| s.parse().ok()).unwrap_or(4);
+                    (*tick, numerator, denominator)
+                })
+                .collect();
+            Some(BarTimeMap::from_time_signatures(ticks_per_beat, &time_sigs))
+        } else {
+            None
+        };
+
         // 4. 构建轨道DSL
         let mut track_dsl_parts = Vec::new();
-        
+
         for (track_idx, track) in smf.tracks.iter().enumerate() {
-            let track_dsl = self.build_track_dsl(track, track_idx, ticks_per_beat);
+            let track_dsl = self.build_track_dsl(track, track_idx, ticks_per_beat, bar_map.as_ref(), grid);
             if !track_dsl.is_empty() {
                 track_dsl_parts.push(track_dsl);
             }
@@ -418,9 +1012,23 @@ impl MidiQuantizer {
     }
     
 /// 构建轨道DSL - 修复版：NoteOff 事件独立记录
-fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_barter: f64) -> String {
+fn build_track_dsl(
+    &self,
+    track: &midly::Track,
+    track_idx: usize,
+    ticks_per_barter: f64,
+    bar_map: Option<&BarTimeMap>,
+    grid: Option<&GridQuantizeConfig>,
+) -> String {
     let mut events: Vec<String> = Vec::new();
     let mut current_tick = 0u32;
+    // 位置格式化：开启小节寻址时输出 "小节:拍:tick"，否则输出绝对拍数浮点值
+    let pos = |tick: u32| -> String {
+        match bar_map {
+            Some(map) => map.format_bar_beat_tick(tick),
+            None => format!("{}", tick as f64 / ticks_per_barter),
+        }
+    };
 
     // 1. 先扫一遍，把 NoteOn/NoteOff 都记下来
     let mut note_events = Vec::new(); // (tick, is_on, note, channel, velocity)
@@ -443,6 +1051,13 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
         }
     }
 
+    // 1.5 若启用网格量化，把每个 NoteOn/NoteOff 的 tick 吸附到最近的网格线（onset 优先处理）
+    if let Some(cfg) = grid {
+        for (tick, _, _, _, _) in note_events.iter_mut() {
+            *tick = cfg.snap(*tick);
+        }
+    }
+
     // 2. 按 (note, ch) 分组 FIFO 配对
     let mut pairs: std::collections::HashMap<(u8, u8), std::collections::VecDeque<(u32, bool, u8)>> = std::collections::HashMap::new();
     for (tick, is_on, note, ch, vel) in note_events {
@@ -451,50 +1066,111 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
              .push_back((tick, is_on, vel));
     }
 
-    // 3. 生成 DSL 行
-    let mut dsl_parts = Vec::new();
+    // 3. 生成事件列表：(排序用tick, 优先级, token)，而非直接拼 DSL 行——
+    //    真正的输出顺序交给第 5 步的全局稳定排序决定，不依赖本处 HashMap 的遍历顺序。
+    //    优先级：meta=0，note_off=1，其余（note_on/cc/pb/prog/sysex）=2，
+    //    同一 tick 下 meta 最先、note_off 先于 note_on，这样同一输入总是产出同样的 token 顺序。
+    const PRIO_META: u8 = 0;
+    const PRIO_NOTE_OFF: u8 = 1;
+    const PRIO_DEFAULT: u8 = 2;
+
+    let mut emission: Vec<(u32, u8, String)> = Vec::new();
+    // 先按 (note, ch) 排好再遍历，同一 (tick, 优先级) 下的多个音符（如和弦）才有固定的先后顺序
+    let mut pairs: Vec<_> = pairs.into_iter().collect();
+    pairs.sort_by_key(|(key, _)| *key);
     for ((note, ch), mut deque) in pairs {
         deque.make_contiguous().sort_by_key(|&(t, _, _)| t);
         while let Some((start_tick, is_on, vel)) = deque.pop_front() {
             if !is_on { continue; } // 没配对的 NoteOff 忽略
-            let start_beat = start_tick as f64 / ticks_per_barter;
+            let start_beat = pos(start_tick);
             let note_name = midi_to_name(note);
-            dsl_parts.push(format!("[{},{},{},ch{}]", note_name, start_beat, vel, ch));
+            let note_on_token = format!("[{},{},{},ch{}]", note_name, start_beat, vel, ch);
 
             // 找下一个 NoteOff
-            if let Some((end_tick, _, _)) = deque.pop_front() {
-                let end_beat = end_tick as f64 / ticks_per_barter;
-                dsl_parts.push(format!("[~{},{},ch{}]", note_name, end_beat, ch));
+            if let Some((mut end_tick, _, _)) = deque.pop_front() {
+                // 网格量化下，吸附可能让首尾落在同一格甚至反转，钳制为至少一个网格步长
+                if let Some(cfg) = grid {
+                    let min_end = start_tick + cfg.grid_step.round() as u32;
+                    if end_tick < min_end {
+                        end_tick = min_end;
+                    }
+                }
+                let duration_ticks = end_tick.saturating_sub(start_tick);
+                // 时值恰好落在符号时值网格上时，用内联写法替代独立的 NoteOff 事件
+                if let Some(code) = beats_to_duration_code(duration_ticks, ticks_per_barter) {
+                    let combined = format!("[{},{},{},ch{},{}]", note_name, start_beat, vel, ch, code);
+                    emission.push((start_tick, PRIO_DEFAULT, combined));
+                } else {
+                    emission.push((start_tick, PRIO_DEFAULT, note_on_token));
+                    let end_beat = pos(end_tick);
+                    emission.push((end_tick, PRIO_NOTE_OFF, format!("[~{},{},ch{}]", note_name, end_beat, ch)));
+                }
             } else {
-                // 真的没有 NoteOff
-                dsl_parts.push(format!("[~{},999.0,ch{}]", note_name, ch));
+                // 真的没有 NoteOff：排在本轨道最后
+                emission.push((start_tick, PRIO_DEFAULT, note_on_token));
+                emission.push((u32::MAX, PRIO_NOTE_OFF, format!("[~{},999.0,ch{}]", note_name, ch)));
             }
         }
     }
 
     // 4. 其余事件（CC、PB、Text…）按原逻辑补在后面
     current_tick = 0;
+    let mut track_name: Option<String> = None;
     for evt in track {
         current_tick += evt.delta.as_int();
-        let beat = current_tick as f64 / ticks_per_barter;
+        let beat = pos(current_tick);
         if let midly::TrackEventKind::Midi { channel, message } = &evt.kind {
             match message {
                 midly::MidiMessage::Controller { controller, value } => {
-                    dsl_parts.push(format!("[cc,{},{},{},ch{}]", controller.as_int(), beat, value.as_int(), channel.as_int()));
+                    emission.push((current_tick, PRIO_DEFAULT, format!("[cc,{},{},{},ch{}]", controller.as_int(), beat, value.as_int(), channel.as_int())));
                 }
                 midly::MidiMessage::PitchBend { bend } => {
                     let val = bend.as_int() as i32 - 8192;
-                    dsl_parts.push(format!("[pb,{},{},ch{}]", beat, val, channel.as_int()));
+                    emission.push((current_tick, PRIO_DEFAULT, format!("[pb,{},{},ch{}]", beat, val, channel.as_int())));
+                }
+                midly::MidiMessage::ProgramChange { program } => {
+                    let name = gm_program_to_name(program.as_int());
+                    emission.push((current_tick, PRIO_DEFAULT, format!("[prog,{},\"{}\",ch{}]", beat, name, channel.as_int())));
+                }
+                _ => {}
+            }
+        } else if let midly::TrackEventKind::SysEx(data) = &evt.kind {
+            // 去掉末尾的 0xF7 终止符，与 write_event_data 的写入方式对应
+            let payload = data.strip_suffix(&[0xF7]).unwrap_or(data);
+            let hex: String = payload.iter().map(|b| format!("{:02X}", b)).collect();
+            emission.push((current_tick, PRIO_DEFAULT, format!("[sysex,{},{}]", beat, hex)));
+        } else if let midly::TrackEventKind::Meta(meta) = &evt.kind {
+            match meta {
+                MetaMessage::Text(text) => {
+                    emission.push((current_tick, PRIO_META, format!("[text,{},\"{}\"]", beat, self.escape_string(text))));
+                }
+                MetaMessage::Lyric(text) => {
+                    emission.push((current_tick, PRIO_META, format!("[lyric,{},\"{}\"]", beat, self.escape_string(text))));
+                }
+                MetaMessage::Marker(text) => {
+                    emission.push((current_tick, PRIO_META, format!("[marker,{},\"{}\"]", beat, self.escape_string(text))));
+                }
+                MetaMessage::TrackName(name) => {
+                    track_name = Some(self.escape_string(name));
                 }
                 _ => {}
             }
         }
     }
 
+    // 5. 全局稳定排序：按 (tick, 优先级) 排序，相同键下保持原有相对顺序（Vec::sort_by_key 是稳定排序），
+    //    从而让输出顺序只取决于事件本身的时间位置，与任何 HashMap 遍历顺序无关
+    emission.sort_by_key(|(tick, priority, _)| (*tick, *priority));
+    let dsl_parts: Vec<String> = emission.into_iter().map(|(_, _, token)| token).collect();
+
     if dsl_parts.is_empty() {
         String::new()
     } else {
-        format!("Track{}: {}", track_idx, dsl_parts.join(" "))
+        match track_name {
+            // 轨道名以头部形式附在 "TrackN:" 之后，不占用独立事件 token
+            Some(name) => format!("Track{}(\"{}\"): {}", track_idx, name, dsl_parts.join(" ")),
+            None => format!("Track{}: {}", track_idx, dsl_parts.join(" ")),
+        }
     }
 }
 
@@ -533,7 +1209,20 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
     }
 
     /// 生成内部实现：DSL字符串 -> MIDI二进制
-    fn generate_internal(&self, dsl: &str) -> Result<Buffer> {
+    fn generate_internal(
+        &self,
+        dsl: &str,
+        reset_mode: Option<&str>,
+        use_running_status: bool,
+        _use_bar_addressing: bool,
+        min_cc_interval_beats: f64,
+    ) -> Result<Buffer> {
+        // 0. "小节:拍:tick"寻址本就是"\d+\.\d+"拍号之外的可选写法，无需显式开关即可
+        // 在任意事件里使用：先把轨道行里出现的 M:B:T 位置统一换算成绝对拍数浮点值，
+        // 下游的验证和解析就都只需要认识传统的 `\d+\.\d+` 格式
+        let normalized = self.normalize_bar_addressing(dsl);
+        let dsl: &str = &normalized;
+
         // 1. 验证DSL
         let errors = self.validate_dsl_internal(dsl);
         if !errors.is_empty() {
@@ -542,22 +1231,27 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
                 format!("DSL 验证失败: {}", errors.join("; ")),
             ));
         }
-        
+
         // 2. 解析Timeline获取全局参数
         let (global_events, tracks_events) = self.parse_dsl_structure(dsl);
         
         // 3. 创建MIDI文件
         let ticks_per_quarter = self.extract_tpb_from_timeline(dsl).unwrap_or(DEFAULT_TPB);
-        let mut midi_file = MidiFile::new(1, ticks_per_quarter);
+        let mut midi_file = MidiFile::new(1, ticks_per_quarter, use_running_status);
         
         // 4. 创建元数据轨道（轨道0）
-        let conductor_track = self.build_conductor_track(&global_events, ticks_per_quarter as f64);
+        let conductor_track =
+            self.build_conductor_track(&global_events, ticks_per_quarter as f64, reset_mode);
         midi_file.add_track(conductor_track);
         
         // 5. 创建音乐轨道
         for (track_idx, events) in tracks_events.iter().enumerate() {
-            let track_events =
-                self.convert_dsl_events_to_midi(events, f64::from(ticks_per_quarter), track_idx);
+            let track_events = self.convert_dsl_events_to_midi(
+                events,
+                f64::from(ticks_per_quarter),
+                track_idx,
+                min_cc_interval_beats,
+            );
             midi_file.add_track(track_events);
         }
         
@@ -565,6 +1259,98 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
         let bytes = midi_file.to_bytes();
         Ok(bytes.into())
     }
+
+    /// 多轨归并导出内部实现：把 Timeline 的全局事件和所有轨道按绝对 tick 归并成一条
+    /// Format-0 单轨事件流
+    fn export_merged_internal(&self, dsl: &str, bar_limit: u32) -> Result<Buffer> {
+        let normalized = self.normalize_bar_addressing(dsl);
+        let dsl: &str = &normalized;
+
+        let errors = self.validate_dsl_internal(dsl);
+        if !errors.is_empty() {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("DSL 验证失败: {}", errors.join("; ")),
+            ));
+        }
+
+        let (global_events, tracks_events) = self.parse_dsl_structure(dsl);
+        let ticks_per_quarter = self.extract_tpb_from_timeline(dsl).unwrap_or(DEFAULT_TPB);
+        let tpq = f64::from(ticks_per_quarter);
+
+        // 小节上限依据 Timeline 的拍号变更换算成 tick 数：第 bar_limit 小节结束、
+        // 即第 (bar_limit+1) 小节第 1 拍 tick 0 处的绝对 tick
+        let time_sigs = self.extract_time_signatures(dsl);
+        let bar_map = BarTimeMap::from_time_signatures(tpq, &time_sigs);
+        let bar_limit_ticks = bar_map.bar_beat_tick_to_tick(bar_limit.saturating_add(1), 1, 0);
+
+        let merged_track =
+            self.merge_tracks_internal(&global_events, &tracks_events, tpq, bar_limit_ticks);
+
+        let mut midi_file = MidiFile::new(0, ticks_per_quarter, false);
+        midi_file.add_track(merged_track);
+        Ok(midi_file.to_bytes().into())
+    }
+
+    /// 多轨按绝对 tick 归并：给 Timeline 的全局事件（tempo/time_sig）和每条音乐轨道各建一个
+    /// 按 (tick, 优先级) 排好序的 peekable 队列（类比 Polyrhythmix 按声部 peekable 归并），
+    /// 每一步都从所有队首里选 tick 最小的弹出；tick 相同时优先弹出序号小的队列（全局事件
+    /// 队列排在最前，所以 tick 相同时 tempo/拍号先于音符），同一轨道内部 NoteOff 优先于
+    /// NoteOn，让瞬时衔接的音符干净地先断后接。超过 `bar_limit_ticks` 的事件直接丢弃，作为
+    /// 导出的硬上限。
+    fn merge_tracks_internal(
+        &self,
+        global_events: &[(String, f64, Vec<String>)],
+        tracks_events: &[Vec<(usize, String, f64, Vec<String>)>],
+        tpb: f64,
+        bar_limit_ticks: u32,
+    ) -> Vec<TrackEvent> {
+        fn event_priority(e: &MidiEventType) -> u8 {
+            match e {
+                MidiEventType::NoteOff { .. } => 0,
+                _ => 1,
+            }
+        }
+
+        let mut queues: Vec<std::iter::Peekable<std::vec::IntoIter<(u32, MidiEventType)>>> =
+            vec![self.global_events_to_abs(global_events, tpb).into_iter().peekable()];
+        queues.extend(tracks_events.iter().map(|events| {
+            let mut abs = self.convert_dsl_events_to_midi_abs(events, tpb, DEFAULT_MIN_CC_INTERVAL_BEATS);
+            abs.sort_by_key(|(tick, evt)| (*tick, event_priority(evt)));
+            abs.into_iter().peekable()
+        }));
+
+        let mut merged: Vec<(u32, MidiEventType)> = Vec::new();
+        loop {
+            let mut best: Option<(usize, u32)> = None;
+            for (idx, queue) in queues.iter_mut().enumerate() {
+                if let Some((tick, _)) = queue.peek() {
+                    if *tick > bar_limit_ticks {
+                        continue;
+                    }
+                    if best.map_or(true, |(_, best_tick)| *tick < best_tick) {
+                        best = Some((idx, *tick));
+                    }
+                }
+            }
+            let Some((idx, _)) = best else { break };
+            if let Some(event) = queues[idx].next() {
+                merged.push(event);
+            }
+        }
+
+        let mut track = vec![TrackEvent {
+            delta_time: 0,
+            event_type: MidiEventType::TrackName("Merged".to_string()),
+        }];
+        let mut last_tick = 0;
+        for (abs, evt) in merged {
+            let delta = abs.saturating_sub(last_tick);
+            last_tick = abs;
+            track.push(TrackEvent { delta_time: delta, event_type: evt });
+        }
+        track
+    }
     
     /// 1. 解析 DSL 为：全局事件 + 各轨道事件（均带 orig_idx）
     fn parse_dsl_structure(
@@ -579,7 +1365,9 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
 
         // 事件正则表
         let patterns = [
-            (r#"\[([a-z]#?\d+),(\d+\.\d+),(\d+),ch(\d+)\]"#, "note_on"),
+            (r#"\[([a-z]#?\d+),(\d+\.\d+),(\d+),ch(\d+)(?:,([whqestx](?:\.|t)?))?\]"#, "note_on"),
+            // 冒号写法的符号时值：[c4,beat,vel,chN:len]，len 为 "w/h/q/e/s/t/x" + 可选附点 + 可选 "/N" 连音
+            (r#"\[([a-z]#?\d+),(\d+\.\d+),(\d+),ch(\d+):([whqestx]\.?(?:/\d+)?)\]"#, "note_on"),
             (r#"\[\~([a-z]#?\d+),(\d+\.\d+),ch(\d+)\]"#, "note_off"),
             (r#"\[cc,(\d+),(\d+\.\d+),(\d+),ch(\d+)\]"#, "cc"),
             (r#"\[pb,(\d+\.\d+),(-?\d+),ch(\d+)\]"#, "pb"),
@@ -588,6 +1376,9 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
             (r#"\[text,(\d+\.\d+),"([^"]+)"\]"#, "text"),
             (r#"\[lyric,(\d+\.\d+),"([^"]+)"\]"#, "lyric"),
             (r#"\[marker,(\d+\.\d+),"([^"]+)"\]"#, "marker"),
+            (r#"\[(?:prog|program),(\d+\.\d+),(.+?),ch(\d+)\]"#, "prog"),
+            (r#"\[sysex,(\d+\.\d+),([0-9A-Fa-f ]+)\]"#, "sysex"),
+            (r#"\[ccramp,(\d+\.\d+),(\d+\.\d+),(\d+),(\d+),(\d+),ch(\d+)\]"#, "ccramp"),
         ];
 
         for line in dsl.lines() {
@@ -599,7 +1390,13 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
                     if let Ok(re) = regex::Regex::new(pattern) {
                         // 同一行内按出现顺序给序号
                         for (idx, cap) in re.captures_iter(line).enumerate() {
-                            let beat = cap[2].parse::<f64>().unwrap();
+                            // 音符开/关和 "cc" 的第一个捕获组是音名/控制器号，拍号落在 cap[2]；
+                            // 其余 token（pb/at/atc/text/lyric/marker/prog/sysex/ccramp）的拍号（起始拍）落在 cap[1]
+                            let beat = if *typ == "note_on" || *typ == "note_off" || *typ == "cc" {
+                                cap[2].parse::<f64>().unwrap()
+                            } else {
+                                cap[1].parse::<f64>().unwrap()
+                            };
                             let params: Vec<String> = cap
                                 .iter()
                                 .skip(1)
@@ -614,6 +1411,85 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
         }
         (global_events, tracks_events)
     }
+
+    /// 从 Timeline 收集拍号变更列表，格式与 `BarTimeMap::from_time_signatures` 一致：
+    /// (起始tick, 分子, 分母)，供 `normalize_bar_addressing`/`check_mbt_addressing` 共用
+    fn extract_time_signatures(&self, dsl: &str) -> Vec<(u32, u8, u8)> {
+        let mut global_events = Vec::new();
+        for line in dsl.lines() {
+            if line.starts_with("Timeline:") {
+                self.parse_timeline_events(line, &mut global_events);
+            }
+        }
+        global_events
+            .iter()
+            .filter(|(typ, _, _)| typ == "time_sig")
+            .map(|(_, tick, params)| {
+                let numerator = params.first().and_then(|s| s.parse().ok()).unwrap_or(4);
+                let denominator = params.get(1).and_then(|s| s.parse().ok()).unwrap_or(4);
+                (*tick as u32, numerator, denominator)
+            })
+            .collect()
+    }
+
+    /// 把整段 DSL 里轨道行中所有"小节:拍:tick"位置，依据 Timeline 已有的拍号变更
+    /// 换算成绝对拍数浮点值（文本替换）。换算后的 DSL 可以直接交给既有的验证和
+    /// `\d+\.\d+` 事件正则表解析，下游无需再感知小节寻址。无需预先开启任何开关：
+    /// 只要轨道行里出现 "M:B:T" 形状的文本就会被换算，不影响本就是 `\d+\.\d+` 的拍号。
+    fn normalize_bar_addressing(&self, dsl: &str) -> String {
+        let ticks_per_quarter = self.extract_tpb_from_timeline(dsl).unwrap_or(DEFAULT_TPB) as f64;
+        let time_sigs = self.extract_time_signatures(dsl);
+        let bar_re = regex::Regex::new(r"(\d+):(\d+):(\d+)").unwrap();
+
+        dsl.lines()
+            .map(|line| {
+                if !line.starts_with("Track") {
+                    return line.to_string();
+                }
+                bar_re
+                    .replace_all(line, |cap: &regex::Captures| {
+                        let bar: u32 = cap[1].parse().unwrap_or(1);
+                        let beat: u32 = cap[2].parse().unwrap_or(1);
+                        let tick: u32 = cap[3].parse().unwrap_or(0);
+                        match mbt_to_beat(bar, beat, tick, ticks_per_quarter, &time_sigs) {
+                            // `{:?}` 让整数拍也带小数点（"4.0" 而非 "4"），才能匹配 `\d+\.\d+`
+                            Some(abs_beat) => format!("{:?}", abs_beat),
+                            // 非法地址（小节/拍为 0，或 tick 越界）：原样保留，交给
+                            // check_mbt_addressing 在换算前报出具体错误
+                            None => format!("{}:{}:{}", bar, beat, tick),
+                        }
+                    })
+                    .into_owned()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// 检查"小节:拍:tick"寻址本身的合法性：小节、拍均为 1 起，tick 不能超出该拍所在
+    /// 拍号区间每拍的 tick 数。必须在 `normalize_bar_addressing` 换算之前对原始 DSL 调用，
+    /// 换算之后非法地址就已经无法辨认了。
+    fn check_mbt_addressing(&self, dsl: &str, errors: &mut Vec<String>) {
+        let ticks_per_quarter = self.extract_tpb_from_timeline(dsl).unwrap_or(DEFAULT_TPB) as f64;
+        let time_sigs = self.extract_time_signatures(dsl);
+        let track_re = regex::Regex::new(r#"Track(\d+)(?:\("[^"]*"\))?: (.+)"#).unwrap();
+        let mbt_re = regex::Regex::new(r"(\d+):(\d+):(\d+)").unwrap();
+
+        for cap in track_re.captures_iter(dsl) {
+            let track_idx = &cap[1];
+            let track_content = &cap[2];
+            for mbt_cap in mbt_re.captures_iter(track_content) {
+                let bar: u32 = mbt_cap[1].parse().unwrap_or(0);
+                let beat: u32 = mbt_cap[2].parse().unwrap_or(0);
+                let tick: u32 = mbt_cap[3].parse().unwrap_or(0);
+                if mbt_to_beat(bar, beat, tick, ticks_per_quarter, &time_sigs).is_none() {
+                    errors.push(format!(
+                        "Track{}: 非法的小节:拍:tick 寻址 {}:{}:{}（小节/拍须从 1 开始，tick 须小于该拍的 tick 数）",
+                        track_idx, bar, beat, tick
+                    ));
+                }
+            }
+        }
+    }
     
     /// 解析时间线事件
     fn parse_timeline_events(&self, timeline_line: &str, events: &mut Vec<(String, f64, Vec<String>)>) {
@@ -651,29 +1527,22 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
         }
     }
     
-    /// 构建指挥轨道（元数据轨道）
-    fn build_conductor_track(&self, global_events: &[(String, f64, Vec<String>)], tpb: f64) -> Vec<TrackEvent> {
-        let mut events = Vec::new();
-        
-        // 添加轨道名
-        events.push(TrackEvent {
-            delta_time: 0,
-            event_type: MidiEventType::TrackName("Conductor Track".to_string()),
-        });
-        
-        // 按时间排序
+    /// 把 Timeline 解析出的全局事件（tempo/time_sig）换算成 (绝对 tick, MidiEventType) 列表，
+    /// 按 tick 升序排好 —— 供 `build_conductor_track`（Format-1 指挥轨道）和
+    /// `merge_tracks_internal`（Format-0 归并导出）共用
+    fn global_events_to_abs(
+        &self,
+        global_events: &[(String, f64, Vec<String>)],
+        tpb: f64,
+    ) -> Vec<(u32, MidiEventType)> {
         let mut sorted_events: Vec<(u32, &(String, f64, Vec<String>))> = global_events
             .iter()
             .map(|ev| ((ev.1 * tpb) as u32, ev))
             .collect();
         sorted_events.sort_by_key(|(tick, _)| *tick);
-        
-        // 添加事件
-        let mut last_tick = 0;
+
+        let mut result = Vec::new();
         for (tick, (typ, _, params)) in sorted_events {
-            let delta = tick.saturating_sub(last_tick);
-            last_tick = tick;
-            
             let event = match typ.as_str() {
                 "tempo" => {
                     let tempo: u32 = params[0].parse().unwrap();
@@ -684,7 +1553,7 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
                     let denominator: u8 = params[1].parse().unwrap_or(4);
                     let clocks_per_click: u8 = params.get(2).and_then(|s| s.parse().ok()).unwrap_or(24);
                     let thirty_seconds_per_quarter: u8 = params.get(3).and_then(|s| s.parse().ok()).unwrap_or(8);
-                    
+
                     MidiEventType::TimeSignature {
                         numerator,
                         denominator,
@@ -694,13 +1563,54 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
                 }
                 _ => continue,
             };
-            
+            result.push((tick, event));
+        }
+        result
+    }
+
+    /// 构建指挥轨道（元数据轨道）
+    /// `reset_mode` 非空时，在轨道名之后插入对应的设备初始化 SysEx（"gm"/"gs"/"xg"，大小写不敏感）
+    fn build_conductor_track(
+        &self,
+        global_events: &[(String, f64, Vec<String>)],
+        tpb: f64,
+        reset_mode: Option<&str>,
+    ) -> Vec<TrackEvent> {
+        let mut events = Vec::new();
+
+        // 添加轨道名
+        events.push(TrackEvent {
+            delta_time: 0,
+            event_type: MidiEventType::TrackName("Conductor Track".to_string()),
+        });
+
+        // 按需插入设备初始化 SysEx（位于 tick 0，先于所有 tempo/拍号事件）
+        if let Some(mode) = reset_mode {
+            let payload = match mode.to_lowercase().as_str() {
+                "gm" => Some(GM_ON_SYSEX.to_vec()),
+                "gs" => Some(GS_ON_SYSEX.to_vec()),
+                "xg" => Some(XG_ON_SYSEX.to_vec()),
+                _ => None,
+            };
+            if let Some(payload) = payload {
+                events.push(TrackEvent {
+                    delta_time: 0,
+                    event_type: MidiEventType::SysEx(payload),
+                });
+            }
+        }
+
+        // 添加事件
+        let mut last_tick = 0;
+        for (tick, event) in self.global_events_to_abs(global_events, tpb) {
+            let delta = tick.saturating_sub(last_tick);
+            last_tick = tick;
             events.push(TrackEvent {
                 delta_time: delta,
                 event_type: event,
             });
         }
-        
+
         events
     }
     
@@ -715,16 +1625,26 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
             errors.push(format!("Timeline 必须且只能出现一次，当前出现 {} 次", timeline_count));
         }
 
-        // 2. 检查时间顺序 - 增强版：检查音符配对
+        // 1.5 检查"小节:拍:tick"寻址本身的合法性（必须在换算成绝对拍数之前做，
+        // 换算会把非法地址原样保留，但语义上已经不是合法的 M:B:T 了）
+        self.check_mbt_addressing(dsl, &mut errors);
+
+        // 2. 把轨道行里出现的"小节:拍:tick"统一换算成绝对拍数浮点值，下面的检查
+        // 就都只需要认识 `\d+\.\d+` 这一种拍号形式了。无论从 `generate()` 还是
+        // `validate_dsl()`/`validate_dsl_with_details()` 进来都会执行这一步。
+        let normalized = self.normalize_bar_addressing(dsl);
+        let dsl: &str = &normalized;
+
+        // 3. 检查时间顺序 - 增强版：检查音符配对
         self.check_note_pairing(dsl, &mut errors);
 
-        // 3. 轨道级分离检查（人声/旋律强制分离）
+        // 4. 轨道级分离检查（人声/旋律强制分离）
         self.check_vocal_melody_separation(dsl, &mut errors);
 
-        // 4. 检查参数范围
+        // 5. 检查参数范围
         self.check_parameter_ranges(dsl, &mut errors);
 
-        // 5. 检查事件语法
+        // 6. 检查事件语法
         self.check_event_syntax(dsl, &mut errors);
 
         errors
@@ -834,13 +1754,39 @@ fn build_track_dsl(&self, track: &midly::Track, track_idx: usize, ticks_per_bart
         events
     }
 
-   /// DSL 事件 → MIDI 事件（轨道内 FIFO，NoteOff 独立输出）
+   /// DSL 事件 → MIDI 事件（轨道内 FIFO，NoteOff 独立输出），生成排好序的 TrackEvent 列表
 fn convert_dsl_events_to_midi(
     &self,
     dsl_events: &[(usize, String, f64, Vec<String>)], // (orig_idx, typ, beat, params)
     tpb: f64,
     _track_idx: usize,
+    min_cc_interval_beats: f64,
 ) -> Vec<TrackEvent> {
+    let midi = self.convert_dsl_events_to_midi_abs(dsl_events, tpb, min_cc_interval_beats);
+
+    let mut track = vec![TrackEvent {
+        delta_time: 0,
+        event_type: MidiEventType::TrackName("Track 0".to_string()),
+    }];
+    let mut last_tick = 0;
+    for (abs, evt) in midi {
+        let delta = abs.saturating_sub(last_tick);
+        last_tick = abs;
+        track.push(TrackEvent { delta_time: delta, event_type: evt });
+    }
+    track
+}
+
+/// `convert_dsl_events_to_midi` 的共用核心：DSL 事件 → (绝对tick, MidiEventType) 列表，
+/// 按 tick 升序排好，但不做 delta 转换 —— 供 `convert_dsl_events_to_midi`（单轨生成）
+/// 和 `merge_tracks_internal`（多轨按 tick 归并）共用。`min_cc_interval_beats` 透传给
+/// `expand_cc_ramp`，控制 `[ccramp,...]` 插值点的最小间距。
+fn convert_dsl_events_to_midi_abs(
+    &self,
+    dsl_events: &[(usize, String, f64, Vec<String>)], // (orig_idx, typ, beat, params)
+    tpb: f64,
+    min_cc_interval_beats: f64,
+) -> Vec<(u32, MidiEventType)> {
     use MidiEventType::{NoteOff, NoteOn};
 
     // 1. 按 (beat, orig_idx) 排序
@@ -861,6 +1807,18 @@ fn convert_dsl_events_to_midi(
                 let note = &params[0];
                 let ch   = &params[3];
                 let vel: u8 = params[2].parse().unwrap_or(100);
+
+                // 携带符号时值（如 "q"、"q."、"qt"）的音符自带时长，直接生成配对的 NoteOn/NoteOff，
+                // 不必等待单独的 [~note,...] 结束事件
+                if let Some(dur_beats) = params.get(4).and_then(|code| duration_code_to_beats(code)) {
+                    let ch_num: u8 = ch.parse().unwrap_or(0);
+                    let key = name_to_midi(note);
+                    let end_tick = tick + (dur_beats * tpb).round() as u32;
+                    midi.push((tick, NoteOn { channel: ch_num, note: key, velocity: vel }));
+                    midi.push((end_tick, NoteOff { channel: ch_num, note: key }));
+                    continue;
+                }
+
                 active.entry((note.clone(), ch.clone()))
                       .or_insert_with(VecDeque::new)
                       .push_back((beat, vel));
@@ -880,6 +1838,21 @@ fn convert_dsl_events_to_midi(
                     }
                 }
             }
+            "ccramp" => {
+                // params: [start_beat, end_beat, controller, start_val, end_val, channel]
+                let cfg = CcRampConfig {
+                    start_beat: beat,
+                    end_beat: params[1].parse().unwrap_or(beat),
+                    controller: params[2].parse().unwrap_or(0),
+                    start_val: params[3].parse().unwrap_or(0),
+                    end_val: params[4].parse().unwrap_or(0),
+                    channel: params[5].parse().unwrap_or(0),
+                    min_interval_beats: min_cc_interval_beats,
+                };
+                for (t, e) in self.expand_cc_ramp(&cfg, tpb) {
+                    midi.push((t, e));
+                }
+            }
             _ => { // 其余事件
                 if let Some(e) = self.convert_other_event(typ, tick, &params) {
                     midi.push((tick, e));
@@ -901,21 +1874,43 @@ fn convert_dsl_events_to_midi(
     }
 
     midi.sort_by_key(|(t, _)| *t);
+    midi
+}
 
-    // 4. 生成 TrackEvent
-    let mut track = vec![TrackEvent {
-        delta_time: 0,
-        event_type: MidiEventType::TrackName("Track 0".to_string()),
-    }];
-    let mut last_tick = 0;
-    for (abs, evt) in midi {
-        let delta = abs.saturating_sub(last_tick);
-        last_tick = abs;
-        track.push(TrackEvent { delta_time: delta, event_type: evt });
+/// 展开 `[ccramp,...]`：在 `[start_beat,end_beat]` 区间内对 CC 值做线性插值，
+/// 参考 Ardour/Evoral 的节流策略——插值点之间至少间隔 `cfg.min_interval_beats * tpb` 个 tick，
+/// 且跳过和前一个输出值相同的点，避免缓慢的渐变刷满轨道；终点值 `end_val` 始终精确输出。
+fn expand_cc_ramp(&self, cfg: &CcRampConfig, tpb: f64) -> Vec<(u32, MidiEventType)> {
+    let start_tick = (cfg.start_beat * tpb).round() as u32;
+    let end_tick = (cfg.end_beat * tpb).round() as u32;
+    let min_interval_ticks = ((cfg.min_interval_beats * tpb).round() as u32).max(1);
+
+    let mut points: Vec<(u32, u8)> = Vec::new();
+    let mut last_value: Option<u8> = None;
+
+    if end_tick > start_tick {
+        let span = (end_tick - start_tick) as f64;
+        let mut tick = start_tick;
+        while tick < end_tick {
+            let ratio = (tick - start_tick) as f64 / span;
+            let value = (cfg.start_val as f64 + (cfg.end_val as i32 - cfg.start_val as i32) as f64 * ratio).round() as u8;
+            if last_value != Some(value) {
+                points.push((tick, value));
+                last_value = Some(value);
+            }
+            tick += min_interval_ticks;
+        }
     }
-    track
+    // 终点值不受节流/去重规则影响，始终精确输出
+    points.push((end_tick, cfg.end_val));
+
+    let (controller, channel) = (cfg.controller, cfg.channel);
+    points
+        .into_iter()
+        .map(|(t, value)| (t, MidiEventType::Controller { channel, controller, value }))
+        .collect()
 }
-    
+
     /// 新的音符配对逻辑：按音符名和通道分组
     fn process_note_pairing_with_grouping(
         &self,
@@ -1068,13 +2063,37 @@ fn convert_dsl_events_to_midi(
                 let text = params[1].clone();
                 Some(MidiEventType::Marker(text))
             }
+            "prog" => {
+                let raw = &params[1];
+                let channel_str = &params[2];
+                let channel: u8 = channel_str.strip_prefix("ch").unwrap_or("0").parse().unwrap_or(0);
+
+                let program = if let Some(name) = raw.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+                    gm_name_to_program(name)?
+                } else {
+                    raw.parse().unwrap_or(0)
+                };
+
+                Some(MidiEventType::ProgramChange { channel, program })
+            }
+            "sysex" => {
+                let hex = params[1].replace(' ', "");
+                if hex.len() % 2 != 0 {
+                    return None;
+                }
+                let payload: Option<Vec<u8>> = (0..hex.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+                    .collect();
+                Some(MidiEventType::SysEx(payload?))
+            }
             _ => None,
         }
     }
     
     /// 检查人声/旋律分离
     fn check_vocal_melody_separation(&self, dsl: &str, errors: &mut Vec<String>) {
-        let track_re = regex::Regex::new(r"Track(\d+): (.+)").unwrap();
+        let track_re = regex::Regex::new(r#"Track(\d+)(?:\("[^"]*"\))?: (.+)"#).unwrap();
         let lyric_re = regex::Regex::new(r"\[lyric,").unwrap();
         let note_re = regex::Regex::new(r"\[[a-z]#?\d+,\d+\.\d+,\d+,ch\d+\]").unwrap();
         
@@ -1097,8 +2116,12 @@ fn convert_dsl_events_to_midi(
     
     /// 检查参数范围
     fn check_parameter_ranges(&self, dsl: &str, errors: &mut Vec<String>) {
-        let track_re = regex::Regex::new(r"Track(\d+): (.+)").unwrap();
-        
+        let track_re = regex::Regex::new(r#"Track(\d+)(?:\("[^"]*"\))?: (.+)"#).unwrap();
+        let sysex_re = regex::Regex::new(r"\[sysex,\d+\.\d+,([0-9A-Fa-f ]+)\]").unwrap();
+        let ramp_re = regex::Regex::new(r"\[ccramp,(\d+\.\d+),(\d+\.\d+),(\d+),(\d+),(\d+),ch\d+\]").unwrap();
+        let prog_re = regex::Regex::new(r"\[(?:prog|program),\d+\.\d+,(\d+),ch\d+\]").unwrap();
+        let prog_name_re = regex::Regex::new(r#"\[(?:prog|program),\d+\.\d+,"([^"]+)",ch\d+\]"#).unwrap();
+
         for cap in track_re.captures_iter(dsl) {
             let track_idx = &cap[1];
             let track_content = &cap[2];
@@ -1137,6 +2160,55 @@ fn convert_dsl_events_to_midi(
                     errors.push(format!("Track{}: 音高越界: {}", track_idx, note_name));
                 }
             }
+
+            // 检查 Program Change 音色号范围 (0-127)，数字形式
+            for prog_cap in prog_re.captures_iter(track_content) {
+                if let Ok(program) = prog_cap[1].parse::<u16>() {
+                    if program > 127 {
+                        errors.push(format!("Track{}: Program Change 音色号越界: {}", track_idx, program));
+                    }
+                }
+            }
+
+            // 检查 Program Change 乐器名是否在 General MIDI 128 音色表里
+            for prog_name_cap in prog_name_re.captures_iter(track_content) {
+                let name = &prog_name_cap[1];
+                if gm_name_to_program(name).is_none() {
+                    errors.push(format!("Track{}: 未知的 General MIDI 乐器名: {}", track_idx, name));
+                }
+            }
+
+            // 检查 SysEx 负载必须是偶数长度的十六进制字符串
+            for sysex_cap in sysex_re.captures_iter(track_content) {
+                let hex = sysex_cap[1].replace(' ', "");
+                if hex.len() % 2 != 0 {
+                    errors.push(format!("Track{}: SysEx 负载必须是偶数个十六进制字符: {}", track_idx, &sysex_cap[1]));
+                }
+            }
+
+            // 检查 CC 渐变：控制器号/起止值范围 (0-127)，以及结束拍必须晚于起始拍
+            for ramp_cap in ramp_re.captures_iter(track_content) {
+                let start_beat: f64 = ramp_cap[1].parse().unwrap_or(0.0);
+                let end_beat: f64 = ramp_cap[2].parse().unwrap_or(0.0);
+                if end_beat <= start_beat {
+                    errors.push(format!("Track{}: CC 渐变结束拍必须晚于起始拍: {} <= {}", track_idx, end_beat, start_beat));
+                }
+                if let Ok(controller) = ramp_cap[3].parse::<u16>() {
+                    if controller > 127 {
+                        errors.push(format!("Track{}: CC 渐变控制器号越界: {}", track_idx, controller));
+                    }
+                }
+                if let Ok(start_val) = ramp_cap[4].parse::<u16>() {
+                    if start_val > 127 {
+                        errors.push(format!("Track{}: CC 渐变起始值越界: {}", track_idx, start_val));
+                    }
+                }
+                if let Ok(end_val) = ramp_cap[5].parse::<u16>() {
+                    if end_val > 127 {
+                        errors.push(format!("Track{}: CC 渐变结束值越界: {}", track_idx, end_val));
+                    }
+                }
+            }
         }
     }
     
@@ -1144,6 +2216,8 @@ fn convert_dsl_events_to_midi(
     fn check_event_syntax(&self, dsl: &str, errors: &mut Vec<String>) {
         let valid_event_patterns = [
             r"\[[a-z]#?\d+,\d+\.\d+,\d+,ch\d+\]",
+            r"\[[a-z]#?\d+,\d+\.\d+,\d+,ch\d+,[whqestx](?:\.|t)?\]",
+            r"\[[a-z]#?\d+,\d+\.\d+,\d+,ch\d+:[whqestx]\.?(?:/\d+)?\]",
             r"\[\~[a-z]#?\d+,\d+\.\d+,ch\d+\]",
             r"\[cc,\d+,\d+\.\d+,\d+,ch\d+\]",
             r"\[pb,\d+\.\d+,-?\d+,ch\d+\]",
@@ -1152,6 +2226,10 @@ fn convert_dsl_events_to_midi(
             r#"\[text,\d+\.\d+,"[^"]*"\]"#,
             r#"\[lyric,\d+\.\d+,"[^"]*"\]"#,
             r#"\[marker,\d+\.\d+,"[^"]*"\]"#,
+            r"\[(?:prog|program),\d+\.\d+,\d+,ch\d+\]",
+            r#"\[(?:prog|program),\d+\.\d+,"[^"]*",ch\d+\]"#,
+            r"\[sysex,\d+\.\d+,[0-9A-Fa-f ]+\]",
+            r"\[ccramp,\d+\.\d+,\d+\.\d+,\d+,\d+,\d+,ch\d+\]",
         ];
         
         // 创建正则表达式
@@ -1163,21 +2241,21 @@ fn convert_dsl_events_to_midi(
         }
         
         // 检查每个轨道的事件
+        let track_re = regex::Regex::new(r#"Track(\d+)(?:\("[^"]*"\))?: (.+)"#).unwrap();
         for (line_num, line) in dsl.lines().enumerate() {
-            if line.starts_with("Track") {
-                if let Some(events_part) = line.splitn(2, ':').nth(1) {
-                    for event in events_part.split_whitespace() {
-                        let mut matched = false;
-                        for pattern in &patterns {
-                            if pattern.is_match(event) {
-                                matched = true;
-                                break;
-                            }
-                        }
-                        if !matched && !event.is_empty() {
-                            errors.push(format!("Line {}: 无效的事件语法: {}", line_num + 1, event));
+            if let Some(cap) = track_re.captures(line) {
+                let events_part = &cap[2];
+                for event in events_part.split_whitespace() {
+                    let mut matched = false;
+                    for pattern in &patterns {
+                        if pattern.is_match(event) {
+                            matched = true;
+                            break;
                         }
                     }
+                    if !matched && !event.is_empty() {
+                        errors.push(format!("Line {}: 无效的事件语法: {}", line_num + 1, event));
+                    }
                 }
             }
         }
@@ -1226,20 +2304,95 @@ fn name_to_midi(name: &str) -> u8 {
     ((octave + 1) * 12 + note_index as i32) as u8
 }
 
+/// General MIDI 乐器名 → Program Change 音色号。
+/// 大小写不敏感，且下划线形式（如 "acoustic_grand_piano"）与表中原有的空格形式
+/// （"Acoustic Grand Piano"）视为等价，方便代码里直接写 snake_case 名字。
+fn gm_name_to_program(name: &str) -> Option<u8> {
+    let normalized = name.trim().to_lowercase().replace('_', " ");
+    GM_INSTRUMENT_NAMES
+        .iter()
+        .position(|n| n.to_lowercase() == normalized)
+        .map(|idx| idx as u8)
+}
+
+/// 符号时值表："基础字母[修饰符]" → 以四分音符为 1 拍时的拍数。
+/// 字母含义：w=全音符 h=二分 q=四分 e=八分 s=十六分 t=三十二分 x=六十四分；
+/// 修饰符：'.'=附点（×1.5），末尾的 't'=三连音（×2/3）
+const NOTE_DURATION_TABLE: [(&str, f64); 21] = [
+    ("w", 4.0), ("w.", 6.0), ("wt", 4.0 * 2.0 / 3.0),
+    ("h", 2.0), ("h.", 3.0), ("ht", 2.0 * 2.0 / 3.0),
+    ("q", 1.0), ("q.", 1.5), ("qt", 2.0 / 3.0),
+    ("e", 0.5), ("e.", 0.75), ("et", 0.5 * 2.0 / 3.0),
+    ("s", 0.25), ("s.", 0.375), ("st", 0.25 * 2.0 / 3.0),
+    ("t", 0.125), ("t.", 0.1875), ("tt", 0.125 * 2.0 / 3.0),
+    ("x", 0.0625), ("x.", 0.09375), ("xt", 0.0625 * 2.0 / 3.0),
+];
+
+/// 符号时值代码（如 "q"、"q."、"qt"、"q/3"）→ 以四分音符为单位的拍数。
+/// 先查 `NOTE_DURATION_TABLE` 命中的简单写法，查不到再退化为通用公式解析
+/// （支持 `/N` 任意连音，而不只是 `NOTE_DURATION_TABLE` 里预置的三连音）。
+fn duration_code_to_beats(code: &str) -> Option<f64> {
+    NOTE_DURATION_TABLE.iter().find(|(c, _)| *c == code).map(|(_, beats)| *beats)
+        .or_else(|| parse_symbolic_duration(code))
+}
+
+/// 通用符号时值解析："基础字母" + 可选 "." (附点，×1.5) + 可选 "/N" (N 连音，×2/N)。
+/// 基础字母对应 2 的幂：w→0, h→1, q→2, e→3, s→4, t→5, x→6，
+/// 时值（以四分音符为单位）= 4 / 2^幂。
+fn parse_symbolic_duration(spec: &str) -> Option<f64> {
+    let mut chars = spec.chars();
+    let power = match chars.next()? {
+        'w' => 0, 'h' => 1, 'q' => 2, 'e' => 3, 's' => 4, 't' => 5, 'x' => 6,
+        _ => return None,
+    };
+    let mut beats = 4.0 / 2f64.powi(power);
+
+    let rest = chars.as_str();
+    let rest = match rest.strip_prefix('.') {
+        Some(r) => { beats *= 1.5; r }
+        None => rest,
+    };
+    if let Some(n_str) = rest.strip_prefix('/') {
+        let n: f64 = n_str.parse().ok()?;
+        if n <= 0.0 { return None; }
+        beats *= 2.0 / n;
+    } else if !rest.is_empty() {
+        return None; // 无法识别的后缀
+    }
+    Some(beats)
+}
+
+/// 反向匹配：给定时长（tick 数），若恰好落在某个符号时值的网格上，返回对应代码
+/// （按表中顺序返回第一个命中的代码；同一字母下不带修饰符的基础时值排在最前）
+fn beats_to_duration_code(duration_ticks: u32, ticks_per_quarter: f64) -> Option<&'static str> {
+    NOTE_DURATION_TABLE.iter().find_map(|(code, beats)| {
+        let ticks = (beats * ticks_per_quarter).round() as u32;
+        if ticks == duration_ticks { Some(*code) } else { None }
+    })
+}
+
+/// Program Change 音色号 → General MIDI 乐器名
+fn gm_program_to_name(program: u8) -> &'static str {
+    GM_INSTRUMENT_NAMES
+        .get(program as usize)
+        .copied()
+        .unwrap_or("Acoustic Grand Piano")
+}
+
 // ==================== 测试函数 ====================
 
 /// 快速测试函数
 #[napi]
 pub fn test_quantize(midi_data: Buffer) -> Result<String> {
     let quantizer = MidiQuantizer::new();
-    quantizer.quantize(midi_data)
+    quantizer.quantize(midi_data, None)
 }
 
 /// 快速测试生成函数
 #[napi]
 pub fn test_generate(dsl: String) -> Result<Buffer> {
     let quantizer = MidiQuantizer::new();
-    quantizer.generate(dsl)
+    quantizer.generate(dsl, None, None, None, None)
 }
 
 /// 验证并返回错误详情
@@ -1255,13 +2408,13 @@ pub fn roundtrip_test(midi_data: Buffer) -> Result<String> {
     let quantizer = MidiQuantizer::new();
     
     // 1. MIDI -> DSL
-    let dsl = quantizer.quantize(midi_data)?;
+    let dsl = quantizer.quantize(midi_data, None)?;
     
     // 2. DSL -> MIDI
-    let generated_midi = quantizer.generate(dsl.clone())?;
+    let generated_midi = quantizer.generate(dsl.clone(), None, None, None, None)?;
     
     // 3. 验证生成的MIDI
-    let generated_dsl = quantizer.quantize(generated_midi)?;
+    let generated_dsl = quantizer.quantize(generated_midi, None)?;
     
     // 比较两个DSL
     let original_lines: Vec<&str> = dsl.lines().collect();
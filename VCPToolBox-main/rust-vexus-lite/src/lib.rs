@@ -14,6 +14,52 @@ pub struct SearchResult {
     pub score: f64,
 }
 
+/// `search_filtered` 的元数据过滤条件
+///
+/// 二选一：`allowed_ids` 直接给出允许的 id 集合（调用方已经自己算好了）；
+/// `sql_predicate` 则是一段对 `recover_from_sqlite` 同一张表（由 `table_type` 指定）
+/// 生效的 WHERE 子句，例如 `"file_id IN (SELECT id FROM files WHERE diary_name = 'foo')"`，
+/// 我们会拼成 `SELECT id FROM <table> WHERE <predicate>` 解析成 id 集合。
+#[napi(object)]
+pub struct SearchFilter {
+    pub db_path: Option<String>,
+    pub table_type: Option<String>,
+    pub sql_predicate: Option<String>,
+    pub allowed_ids: Option<Vec<u32>>,
+}
+
+impl SearchFilter {
+    /// 解析为允许的 id 集合
+    fn resolve(&self) -> Result<std::collections::HashSet<u64>> {
+        if let Some(ids) = &self.allowed_ids {
+            return Ok(ids.iter().map(|&id| id as u64).collect());
+        }
+
+        let db_path = self.db_path.as_ref()
+            .ok_or_else(|| Error::from_reason("SearchFilter requires either allowed_ids or db_path+sql_predicate".to_string()))?;
+        let predicate = self.sql_predicate.as_ref()
+            .ok_or_else(|| Error::from_reason("SearchFilter requires sql_predicate when db_path is set".to_string()))?;
+        let table = match self.table_type.as_deref() {
+            Some("tags") => "tags",
+            _ => "chunks",
+        };
+
+        let conn = Connection::open(db_path)
+            .map_err(|e| Error::from_reason(format!("Failed to open DB: {}", e)))?;
+        let sql = format!("SELECT id FROM {} WHERE {}", table, predicate);
+        let mut stmt = conn.prepare(&sql)
+            .map_err(|e| Error::from_reason(format!("Failed to prepare filter query: {}", e)))?;
+        let rows = stmt.query_map([], |row| row.get::<_, i64>(0))
+            .map_err(|e| Error::from_reason(format!("Filter query failed: {}", e)))?;
+
+        let mut ids = std::collections::HashSet::new();
+        for id in rows.flatten() {
+            ids.insert(id as u64);
+        }
+        Ok(ids)
+    }
+}
+
 #[napi(object)]
 pub struct SvdResult {
     pub u: Vec<f64>, // 扁平化的正交基底向量集 (k * dim)
@@ -50,6 +96,99 @@ pub struct VexusStats {
     pub dimensions: u32,
     pub capacity: u32,
     pub memory_usage: u32,
+    /// 是否为 `load_view` 打开的只读 mmap 索引
+    pub is_mapped: bool,
+}
+
+/// 距离度量选择，对应 usearch 的 `MetricKind`
+///
+/// 持久化为索引文件旁的 `.meta` sidecar，这样 `load` 才能重建出同样的度量，
+/// 而不是每次都假设 L2sq。
+#[napi]
+#[derive(Debug, PartialEq, Eq)]
+pub enum VexusMetric {
+    Cosine,
+    Ip,
+    L2sq,
+    Hamming,
+}
+
+impl VexusMetric {
+    fn to_usearch(self) -> usearch::MetricKind {
+        match self {
+            VexusMetric::Cosine => usearch::MetricKind::Cos,
+            VexusMetric::Ip => usearch::MetricKind::IP,
+            VexusMetric::L2sq => usearch::MetricKind::L2sq,
+            VexusMetric::Hamming => usearch::MetricKind::Hamming,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            VexusMetric::Cosine => "cosine",
+            VexusMetric::Ip => "ip",
+            VexusMetric::L2sq => "l2sq",
+            VexusMetric::Hamming => "hamming",
+        }
+    }
+
+    fn from_str(s: &str) -> Self {
+        match s {
+            "cosine" => VexusMetric::Cosine,
+            "ip" => VexusMetric::Ip,
+            "hamming" => VexusMetric::Hamming,
+            _ => VexusMetric::L2sq,
+        }
+    }
+
+    /// 距离 -> 相似度分数，按度量类型区分含义
+    fn distance_to_score(self, dist: f64) -> f64 {
+        match self {
+            // Cosine/IP 的 usearch 距离本身就是 "1 - 相似度"，直接还原即可
+            VexusMetric::Cosine | VexusMetric::Ip => 1.0 - dist,
+            // L2sq 距离没有固定上界，映射到 (0,1] 区间
+            VexusMetric::L2sq | VexusMetric::Hamming => 1.0 / (1.0 + dist),
+        }
+    }
+}
+
+/// 标量量化精度选择，对应 usearch 的 `ScalarKind`
+#[napi]
+#[derive(Debug, PartialEq, Eq)]
+pub enum VexusQuantization {
+    F32,
+    F16,
+    I8,
+    B1,
+}
+
+impl VexusQuantization {
+    fn to_usearch(self) -> usearch::ScalarKind {
+        match self {
+            VexusQuantization::F32 => usearch::ScalarKind::F32,
+            VexusQuantization::F16 => usearch::ScalarKind::F16,
+            VexusQuantization::I8 => usearch::ScalarKind::I8,
+            VexusQuantization::B1 => usearch::ScalarKind::B1,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            VexusQuantization::F32 => "f32",
+            VexusQuantization::F16 => "f16",
+            VexusQuantization::I8 => "i8",
+            VexusQuantization::B1 => "b1",
+        }
+    }
+
+    fn from_str(s: &str) -> Self {
+        match s {
+            "f16" => VexusQuantization::F16,
+            "i8" => VexusQuantization::I8,
+            "b1" => VexusQuantization::B1,
+            _ => VexusQuantization::F32,
+        }
+    }
 }
 
 /// 核心索引结构 (无状态，只存向量)
@@ -57,17 +196,66 @@ pub struct VexusStats {
 pub struct VexusIndex {
     index: Arc<RwLock<Index>>,
     dimensions: u32,
+    metric: VexusMetric,
+    quantization: VexusQuantization,
+    /// 是否通过 `load_view` 以只读 mmap 方式打开；视图索引不可写
+    is_view: bool,
+}
+
+impl VexusIndex {
+    /// 度量/量化持久化的 sidecar 文件路径：`<index_path>.meta`
+    fn meta_path(index_path: &str) -> String {
+        format!("{}.meta", index_path)
+    }
+
+    fn write_meta(index_path: &str, metric: VexusMetric, quantization: VexusQuantization) -> Result<()> {
+        let content = format!("{}\n{}\n", metric.as_str(), quantization.as_str());
+        std::fs::write(Self::meta_path(index_path), content)
+            .map_err(|e| Error::from_reason(format!("Failed to write index metadata: {}", e)))
+    }
+
+    fn read_meta(index_path: &str) -> (VexusMetric, VexusQuantization) {
+        match std::fs::read_to_string(Self::meta_path(index_path)) {
+            Ok(content) => {
+                let mut lines = content.lines();
+                let metric = lines.next().map(VexusMetric::from_str).unwrap_or(VexusMetric::L2sq);
+                let quantization = lines.next().map(VexusQuantization::from_str).unwrap_or(VexusQuantization::F32);
+                (metric, quantization)
+            }
+            // 没有 sidecar（旧索引）时退回历史默认值，保持向后兼容
+            Err(_) => (VexusMetric::L2sq, VexusQuantization::F32),
+        }
+    }
+
+    /// `load_view` 打开的索引是只读 mmap，拒绝一切写操作
+    fn reject_if_view(&self, op: &str) -> Result<()> {
+        if self.is_view {
+            return Err(Error::from_reason(format!(
+                "'{}' is not supported on a mmap-viewed index (load_view); reload with load() to mutate it",
+                op
+            )));
+        }
+        Ok(())
+    }
 }
 
 #[napi]
 impl VexusIndex {
     /// 创建新的空索引
     #[napi(constructor)]
-    pub fn new(dim: u32, capacity: u32) -> Result<Self> {
+    pub fn new(
+        dim: u32,
+        capacity: u32,
+        metric: Option<VexusMetric>,
+        quantization: Option<VexusQuantization>,
+    ) -> Result<Self> {
+        let metric = metric.unwrap_or(VexusMetric::L2sq);
+        let quantization = quantization.unwrap_or(VexusQuantization::F32);
+
         let index = Index::new(&usearch::IndexOptions {
             dimensions: dim as usize,
-            metric: usearch::MetricKind::L2sq, // 余弦相似度通常用 L2sq 或 Cosine (如果是归一化向量，L2sq 等价于 Cosine)
-            quantization: usearch::ScalarKind::F32,
+            metric: metric.to_usearch(),
+            quantization: quantization.to_usearch(),
             connectivity: 16,
             expansion_add: 128,
             expansion_search: 64,
@@ -82,21 +270,37 @@ impl VexusIndex {
         Ok(Self {
             index: Arc::new(RwLock::new(index)),
             dimensions: dim,
+            metric,
+            quantization,
+            is_view: false,
         })
     }
 
     /// 从磁盘加载索引
     /// 注意：移除了 map_path，因为映射关系现在由 SQLite 管理
+    /// 度量/量化以调用方显式传入的参数为准；未传入时从 `<index_path>.meta` sidecar 读取，
+    /// sidecar 不存在（旧索引）时回退到历史默认值 L2sq/F32。
     #[napi(factory)]
-    pub fn load(index_path: String, _unused_map_path: Option<String>, dim: u32, capacity: u32) -> Result<Self> {
+    pub fn load(
+        index_path: String,
+        _unused_map_path: Option<String>,
+        dim: u32,
+        capacity: u32,
+        metric: Option<VexusMetric>,
+        quantization: Option<VexusQuantization>,
+    ) -> Result<Self> {
         // 为了保持 JS 调用签名兼容，保留了 map_path 参数但忽略它
         // 或者你可以修改 JS 里的调用去掉第二个参数
 
+        let (sidecar_metric, sidecar_quantization) = Self::read_meta(&index_path);
+        let metric = metric.unwrap_or(sidecar_metric);
+        let quantization = quantization.unwrap_or(sidecar_quantization);
+
         // 创建空索引配置
         let index = Index::new(&usearch::IndexOptions {
             dimensions: dim as usize,
-            metric: usearch::MetricKind::L2sq,
-            quantization: usearch::ScalarKind::F32,
+            metric: metric.to_usearch(),
+            quantization: quantization.to_usearch(),
             connectivity: 16,
             expansion_add: 128,
             expansion_search: 64,
@@ -120,15 +324,61 @@ impl VexusIndex {
         Ok(Self {
             index: Arc::new(RwLock::new(index)),
             dimensions: dim,
+            metric,
+            quantization,
+            is_view: false,
+        })
+    }
+
+    /// 以只读 mmap 方式加载索引 (`view`)
+    ///
+    /// `load` 会把整个文件读进堆内存再按需 `reserve`，对启动时就要打开的大型日记归档
+    /// 索引来说又慢又吃内存。这里改用 usearch 的 `view`，由 OS 按需分页、近乎零拷贝，
+    /// 搜索可以立即开始。代价是只读：`add`/`remove`/`save` 在视图索引上会直接返回错误，
+    /// `stats` 也会如实报告这是一个 mapped 索引。
+    #[napi(factory)]
+    pub fn load_view(
+        index_path: String,
+        dim: u32,
+        metric: Option<VexusMetric>,
+        quantization: Option<VexusQuantization>,
+    ) -> Result<Self> {
+        let (sidecar_metric, sidecar_quantization) = Self::read_meta(&index_path);
+        let metric = metric.unwrap_or(sidecar_metric);
+        let quantization = quantization.unwrap_or(sidecar_quantization);
+
+        let index = Index::new(&usearch::IndexOptions {
+            dimensions: dim as usize,
+            metric: metric.to_usearch(),
+            quantization: quantization.to_usearch(),
+            connectivity: 16,
+            expansion_add: 128,
+            expansion_search: 64,
+            multi: false,
+        })
+        .map_err(|e| Error::from_reason(format!("Failed to create index wrapper: {:?}", e)))?;
+
+        index
+            .view(&index_path)
+            .map_err(|e| Error::from_reason(format!("Failed to mmap index: {:?}", e)))?;
+
+        Ok(Self {
+            index: Arc::new(RwLock::new(index)),
+            dimensions: dim,
+            metric,
+            quantization,
+            is_view: true,
         })
     }
 
     /// 保存索引到磁盘
     #[napi]
     pub fn save(&self, index_path: String) -> Result<()> {
+        self.reject_if_view("save")?;
+
         let index = self.index.read()
             .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
-        
+
         // 原子写入：先写临时文件，再重命名
         let temp_path = format!("{}.tmp", index_path);
 
@@ -139,12 +389,29 @@ impl VexusIndex {
         std::fs::rename(&temp_path, &index_path)
             .map_err(|e| Error::from_reason(format!("Failed to rename index file: {}", e)))?;
 
+        // usearch 的二进制格式里不带量化/度量信息，另存一份 sidecar 供 load 还原
+        Self::write_meta(&index_path, self.metric, self.quantization)?;
+
         Ok(())
     }
 
+    /// 保存索引到磁盘 (异步版本，大索引的磁盘 I/O 放到线程池，不卡 UI)
+    #[napi]
+    pub fn save_async(&self, index_path: String) -> Result<AsyncTask<SaveTask>> {
+        self.reject_if_view("save_async")?;
+        Ok(AsyncTask::new(SaveTask {
+            index: self.index.clone(),
+            index_path,
+            metric: self.metric,
+            quantization: self.quantization,
+        }))
+    }
+
     /// 单个添加 (JS 循环调用)
     #[napi]
     pub fn add(&self, id: u32, vector: Buffer) -> Result<()> {
+        self.reject_if_view("add")?;
+
         let index = self.index.write()
             .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
 
@@ -179,6 +446,8 @@ impl VexusIndex {
     /// 批量添加 (更高效，建议未来 JS 改用此接口)
     #[napi]
     pub fn add_batch(&self, ids: Vec<u32>, vectors: Buffer) -> Result<()> {
+        self.reject_if_view("add_batch")?;
+
         let index = self.index.write()
             .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
 
@@ -214,6 +483,18 @@ impl VexusIndex {
         Ok(())
     }
 
+    /// 批量添加 (异步版本，在 libuv 线程池里执行，不阻塞事件循环)
+    #[napi]
+    pub fn add_batch_async(&self, ids: Vec<u32>, vectors: Buffer) -> Result<AsyncTask<AddBatchTask>> {
+        self.reject_if_view("add_batch_async")?;
+        Ok(AsyncTask::new(AddBatchTask {
+            index: self.index.clone(),
+            ids,
+            vectors,
+            dimensions: self.dimensions,
+        }))
+    }
+
     /// 搜索
     #[napi]
     pub fn search(&self, query: Buffer, k: u32) -> Result<Vec<SearchResult>> {
@@ -246,22 +527,179 @@ impl VexusIndex {
         for (key, &dist) in matches.keys.iter().zip(matches.distances.iter()) {
             results.push(SearchResult {
                 id: *key as u32,
-                score: 1.0 - dist as f64, // L2sq 距离转相似度分数 (近似)
+                score: self.metric.distance_to_score(dist as f64),
             });
         }
 
         Ok(results)
     }
 
+    /// 搜索 (异步版本，大 `expansion_search` 时搜索本身会比较慢，放到线程池执行)
+    #[napi]
+    pub fn search_async(&self, query: Buffer, k: u32) -> AsyncTask<SearchTask> {
+        AsyncTask::new(SearchTask {
+            index: self.index.clone(),
+            query,
+            k,
+            dimensions: self.dimensions,
+            metric: self.metric,
+        })
+    }
+
+    /// 带元数据过滤的搜索
+    ///
+    /// 先把 `filter` 解析成允许的 id 集合，再反复扩大 usearch 的召回数量
+    /// （`k`, `4k`, `16k`, ...）过滤掉不在集合里的结果，直到凑够 `k` 个幸存者
+    /// 或者索引里已经没有更多候选，这样调用方拿到的 `k` 仍然是精确的 top-k，
+    /// 而不是"先搜全局 top-k 再在 JS 里过滤"导致的结果数不足。
+    #[napi]
+    pub fn search_filtered(&self, query: Buffer, k: u32, filter: SearchFilter) -> Result<Vec<SearchResult>> {
+        let allowed = filter.resolve()?;
+
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let query_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                query.as_ptr() as *const f32,
+                query.len() / std::mem::size_of::<f32>(),
+            )
+        };
+
+        if query_slice.len() != self.dimensions as usize {
+            return Err(Error::from_reason(format!(
+                "Search dimension mismatch: expected {}, got {}. (Check your JS Buffer slicing!)",
+                self.dimensions,
+                query_slice.len()
+            )));
+        }
+
+        let k = k as usize;
+        let total = index.size();
+        let mut fetch = k.max(1);
+        let mut results = Vec::new();
+
+        loop {
+            let matches = index
+                .search(query_slice, fetch)
+                .map_err(|e| Error::from_reason(format!("Search failed: {:?}", e)))?;
+
+            results.clear();
+            for (key, &dist) in matches.keys.iter().zip(matches.distances.iter()) {
+                if allowed.contains(key) {
+                    results.push(SearchResult {
+                        id: *key as u32,
+                        score: self.metric.distance_to_score(dist as f64),
+                    });
+                    if results.len() >= k {
+                        break;
+                    }
+                }
+            }
+
+            if results.len() >= k || fetch >= total || matches.keys.len() < fetch {
+                break;
+            }
+            fetch = (fetch * 4).min(total.max(fetch));
+        }
+
+        Ok(results)
+    }
+
+    /// 混合检索：ANN 向量搜索 + SQLite 全文/LIKE 关键词搜索，用 RRF 融合排序
+    ///
+    /// `chunks` 表里既存了切片文本又存了向量，但查询只会命中其中一路——纯向量搜索
+    /// 漏掉精确的人名/日期，纯关键词搜索漏掉改写后的语义匹配。这里分别取两路的
+    /// 有序 id 列表，再用 Reciprocal Rank Fusion 融合：
+    /// `score(d) = Σ_l 1 / (c + rank_l(d))`，`rank_l` 是 d 在列表 l 里的 1-based 排名，
+    /// 不在某一路里的文档对那一路贡献 0 分。`c`（默认 60）是平滑常数，排名越靠前
+    /// 贡献越大，但不会因为单路的绝对分数量纲不同而失真。
+    #[napi]
+    pub fn hybrid_search(
+        &self,
+        query_vector: Buffer,
+        query_text: String,
+        k: u32,
+        db_path: String,
+    ) -> Result<Vec<SearchResult>> {
+        const RRF_C: f64 = 60.0;
+        let k = k as usize;
+
+        // 向量检索：过采样到 k*4，保证融合阶段有足够候选
+        let vector_fetch = (k * 4).max(k).max(1);
+        let vector_ranked: Vec<u64> = {
+            let index = self.index.read()
+                .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+            let query_slice: &[f32] = unsafe {
+                std::slice::from_raw_parts(
+                    query_vector.as_ptr() as *const f32,
+                    query_vector.len() / std::mem::size_of::<f32>(),
+                )
+            };
+            if query_slice.len() != self.dimensions as usize {
+                return Err(Error::from_reason(format!(
+                    "Search dimension mismatch: expected {}, got {}. (Check your JS Buffer slicing!)",
+                    self.dimensions,
+                    query_slice.len()
+                )));
+            }
+
+            let matches = index
+                .search(query_slice, vector_fetch)
+                .map_err(|e| Error::from_reason(format!("Search failed: {:?}", e)))?;
+            matches.keys.clone()
+        };
+
+        // 关键词检索：对 chunks.content 做 LIKE 匹配，按 rowid 顺序近似作为相关性排序
+        let keyword_ranked: Vec<u64> = {
+            let conn = Connection::open(&db_path)
+                .map_err(|e| Error::from_reason(format!("Failed to open DB: {}", e)))?;
+            let like_pattern = format!("%{}%", query_text.replace(['%', '_'], ""));
+            let mut stmt = conn
+                .prepare("SELECT id FROM chunks WHERE content LIKE ?1 LIMIT ?2")
+                .map_err(|e| Error::from_reason(format!("Failed to prepare keyword query: {}", e)))?;
+            let rows = stmt
+                .query_map(rusqlite::params![like_pattern, vector_fetch as i64], |row| row.get::<_, i64>(0))
+                .map_err(|e| Error::from_reason(format!("Keyword query failed: {}", e)))?;
+
+            let mut ids = Vec::new();
+            for id in rows.flatten() {
+                ids.push(id as u64);
+            }
+            ids
+        };
+
+        // RRF 融合
+        let mut fused: std::collections::HashMap<u64, f64> = std::collections::HashMap::new();
+        for (rank, id) in vector_ranked.iter().enumerate() {
+            *fused.entry(*id).or_insert(0.0) += 1.0 / (RRF_C + (rank + 1) as f64);
+        }
+        for (rank, id) in keyword_ranked.iter().enumerate() {
+            *fused.entry(*id).or_insert(0.0) += 1.0 / (RRF_C + (rank + 1) as f64);
+        }
+
+        let mut scored: Vec<(u64, f64)> = fused.into_iter().collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(k);
+
+        Ok(scored
+            .into_iter()
+            .map(|(id, score)| SearchResult { id: id as u32, score })
+            .collect())
+    }
+
     /// 删除 (按 ID)
     #[napi]
     pub fn remove(&self, id: u32) -> Result<()> {
+        self.reject_if_view("remove")?;
+
         let index = self.index.write()
             .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
-        
+
         index.remove(id as u64)
              .map_err(|e| Error::from_reason(format!("Remove failed: {:?}", e)))?;
-             
+
         Ok(())
     }
 
@@ -276,6 +714,7 @@ impl VexusIndex {
             dimensions: self.dimensions,
             capacity: index.capacity() as u32,
             memory_usage: index.memory_usage() as u32,
+            is_mapped: self.is_view,
         })
     }
 
@@ -301,7 +740,13 @@ impl VexusIndex {
     /// n: 向量数量
     /// max_k: 最大保留的主成分数量
     #[napi]
-    pub fn compute_svd(&self, flattened_vectors: Buffer, n: u32, max_k: u32) -> Result<SvdResult> {
+    pub fn compute_svd(
+        &self,
+        flattened_vectors: Buffer,
+        n: u32,
+        max_k: u32,
+        use_randomized: Option<bool>,
+    ) -> Result<SvdResult> {
         let dim = self.dimensions as usize;
         let n = n as usize;
         let max_k = max_k as usize;
@@ -321,32 +766,44 @@ impl VexusIndex {
             )));
         }
 
-        // 使用 nalgebra 进行 SVD 分解
         // M 是 n x dim 矩阵
         use nalgebra::DMatrix;
         let matrix = DMatrix::from_row_slice(n, dim, vec_slice);
-        
-        // 计算 SVD: M = U * S * V^T
-        // 我们需要的是 V^T 的行，它们是原始空间中的主成分
-        let svd = matrix.svd(false, true);
-        
-        let s = svd.singular_values.as_slice().iter().map(|&x| x as f64).collect::<Vec<_>>();
-        let v_t = svd.v_t.ok_or_else(|| Error::from_reason("Failed to compute V^T matrix".to_string()))?;
-        
-        let k = std::cmp::min(s.len(), max_k);
-        let mut u_flattened = Vec::with_capacity(k * dim);
-        
-        for i in 0..k {
-            let row = v_t.row(i);
-            // nalgebra 的 row view 可能不连续，手动迭代以确保安全
-            for &val in row.iter() {
-                u_flattened.push(val as f64);
+
+        // 精确 SVD 是 O(n·dim·min(n,dim))，EPA 基底重建要跑在成千上万条 chunk 向量上时
+        // 会成为瓶颈。小输入（矩阵本身已经不比要求的 k+oversample 大多少）直接走精确路径，
+        // 否则走随机化截断 SVD，只算 max_k 个主成分。
+        const RANDOM_SVD_THRESHOLD: usize = 512;
+        const OVERSAMPLE: usize = 8;
+        let min_dim = std::cmp::min(n, dim);
+        let should_randomize = use_randomized
+            .unwrap_or_else(|| min_dim > RANDOM_SVD_THRESHOLD && max_k + OVERSAMPLE < min_dim);
+
+        let (s, v_t_rows, k) = if should_randomize {
+            randomized_truncated_svd(&matrix, n, dim, max_k, OVERSAMPLE, 2)
+        } else {
+            // 精确路径：M = U * S * V^T，我们只需要 V^T 的行作为原始空间中的主成分
+            let svd = matrix.svd(false, true);
+            let s = svd.singular_values.as_slice().iter().map(|&x| x as f64).collect::<Vec<_>>();
+            let v_t = svd.v_t.ok_or_else(|| Error::from_reason("Failed to compute V^T matrix".to_string()))?;
+
+            let k = std::cmp::min(s.len(), max_k);
+            let mut rows = Vec::with_capacity(k);
+            for i in 0..k {
+                // nalgebra 的 row view 可能不连续，手动迭代以确保安全
+                rows.push(v_t.row(i).iter().map(|&val| val as f64).collect::<Vec<_>>());
             }
+            (s[..k].to_vec(), rows, k)
+        };
+
+        let mut u_flattened = Vec::with_capacity(k * dim);
+        for row in &v_t_rows {
+            u_flattened.extend_from_slice(row);
         }
 
         Ok(SvdResult {
             u: u_flattened,
-            s: s[..k].to_vec(),
+            s,
             k: k as u32,
             dim: dim as u32,
         })
@@ -544,6 +1001,233 @@ impl VexusIndex {
     }
 }
 
+/// 极简 xorshift64* PRNG，只用来生成随机化 SVD 的高斯投影矩阵，
+/// 避免为了一次性的随机数引入整条 `rand` 依赖链。
+struct XorShiftRng(u64);
+
+impl XorShiftRng {
+    fn new(seed: u64) -> Self {
+        Self(seed | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    fn next_uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Box-Muller 变换：从两个均匀分布采样得到一个标准正态样本
+    fn next_gaussian(&mut self) -> f64 {
+        let u1 = self.next_uniform().max(1e-12);
+        let u2 = self.next_uniform();
+        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+}
+
+/// 随机化截断 SVD：只求 `M`（`n x dim`）的前 `max_k` 个右奇异向量/奇异值
+///
+/// 步骤对应 Halko/Martinsson/Tropp 的随机化 SVD 算法：
+/// 1. 画一个 `dim x (k+p)` 的高斯随机矩阵 Ω（`p` 是过采样量）；
+/// 2. `Y = M·Ω`（`n x (k+p)`），做 `q` 次幂迭代 `Y = M·(Mᵀ·Y)` 提高精度，
+///    每次迭代后都重新做 QR 正交化，避免数值下溢；
+/// 3. 对 `Y` 做 QR 分解取正交基 `Q`（`n x (k+p)`）；
+/// 4. 投影到小矩阵 `B = Qᵀ·M`（`(k+p) x dim`），对 `B` 做精确 SVD；
+/// 5. 返回 `B` 的前 `k` 个奇异值和右奇异向量（`M` 的左奇异向量 `Q·Û` 被丢弃，
+///    因为现有 API 只对外暴露 `V^T` 的行作为基底）。
+fn randomized_truncated_svd(
+    matrix: &nalgebra::DMatrix<f32>,
+    n: usize,
+    dim: usize,
+    max_k: usize,
+    oversample: usize,
+    power_iters: usize,
+) -> (Vec<f64>, Vec<Vec<f64>>, usize) {
+    use nalgebra::DMatrix;
+
+    let min_dim = std::cmp::min(n, dim);
+    // k+p 不能超过矩阵本身的秩上界，否则 QR/SVD 会拿到全是噪声的列
+    let k_plus_p = std::cmp::min(max_k + oversample, min_dim).max(1);
+
+    let m64 = matrix.map(|x| x as f64);
+
+    // 1. 高斯随机矩阵 Ω: dim x (k+p)
+    let mut rng = XorShiftRng::new(0x5eed_abcd_1234_5678 ^ (n as u64) ^ ((dim as u64) << 32));
+    let omega = DMatrix::from_fn(dim, k_plus_p, |_, _| rng.next_gaussian());
+
+    // 2. Y = M·Ω，随后做幂迭代 Y = M·(Mᵀ·Y) 提升精度，每轮重新正交化
+    let mut y = &m64 * &omega;
+    for _ in 0..power_iters {
+        let qr = y.qr();
+        y = qr.q();
+        let z = m64.transpose() * &y;
+        let qr = z.qr();
+        let z_q = qr.q();
+        y = &m64 * &z_q;
+    }
+
+    // 3. QR 分解得到正交基 Q (n x (k+p))
+    let qr = y.qr();
+    let q = qr.q();
+
+    // 4. 投影到小矩阵 B = Qᵀ·M ((k+p) x dim)，对其做精确 SVD
+    let b = q.transpose() * &m64;
+    let svd = b.svd(false, true);
+    let s = svd.singular_values;
+    let v_t = svd.v_t.expect("v_t requested");
+
+    let k = std::cmp::min(std::cmp::min(max_k, s.len()), k_plus_p);
+    let mut rows = Vec::with_capacity(k);
+    for i in 0..k {
+        rows.push(v_t.row(i).iter().copied().collect::<Vec<_>>());
+    }
+
+    (s.as_slice()[..k].to_vec(), rows, k)
+}
+
+/// `add_batch` 的异步版本：锁获取、usearch 写入都在 libuv 线程池里完成
+pub struct AddBatchTask {
+    index: Arc<RwLock<Index>>,
+    ids: Vec<u32>,
+    vectors: Buffer,
+    dimensions: u32,
+}
+
+impl Task for AddBatchTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let index = self.index.write()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let count = self.ids.len();
+        let dim = self.dimensions as usize;
+
+        let vec_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                self.vectors.as_ptr() as *const f32,
+                self.vectors.len() / std::mem::size_of::<f32>(),
+            )
+        };
+
+        if vec_slice.len() != count * dim {
+            return Err(Error::from_reason("Batch size mismatch".to_string()));
+        }
+
+        if index.size() + count >= index.capacity() {
+            let new_cap = ((index.size() + count) as f64 * 1.5) as usize;
+            let _ = index.reserve(new_cap);
+        }
+
+        for (i, id) in self.ids.iter().enumerate() {
+            let start = i * dim;
+            let v = &vec_slice[start..start + dim];
+            index.add(*id as u64, v)
+                .map_err(|e| Error::from_reason(format!("Batch add failed idx {}: {:?}", i, e)))?;
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// `search` 的异步版本：大 `expansion_search` 时查询本身开销较大，放到线程池
+pub struct SearchTask {
+    index: Arc<RwLock<Index>>,
+    query: Buffer,
+    k: u32,
+    dimensions: u32,
+    metric: VexusMetric,
+}
+
+impl Task for SearchTask {
+    type Output = Vec<SearchResult>;
+    type JsValue = Vec<SearchResult>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let query_slice: &[f32] = unsafe {
+            std::slice::from_raw_parts(
+                self.query.as_ptr() as *const f32,
+                self.query.len() / std::mem::size_of::<f32>(),
+            )
+        };
+
+        if query_slice.len() != self.dimensions as usize {
+            return Err(Error::from_reason(format!(
+                "Search dimension mismatch: expected {}, got {}. (Check your JS Buffer slicing!)",
+                self.dimensions,
+                query_slice.len()
+            )));
+        }
+
+        let matches = index
+            .search(query_slice, self.k as usize)
+            .map_err(|e| Error::from_reason(format!("Search failed: {:?}", e)))?;
+
+        let mut results = Vec::with_capacity(matches.keys.len());
+        for (key, &dist) in matches.keys.iter().zip(matches.distances.iter()) {
+            results.push(SearchResult {
+                id: *key as u32,
+                score: self.metric.distance_to_score(dist as f64),
+            });
+        }
+
+        Ok(results)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// `save` 的异步版本：原子写入 + rename + sidecar 都放到线程池
+pub struct SaveTask {
+    index: Arc<RwLock<Index>>,
+    index_path: String,
+    metric: VexusMetric,
+    quantization: VexusQuantization,
+}
+
+impl Task for SaveTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let index = self.index.read()
+            .map_err(|e| Error::from_reason(format!("Lock failed: {}", e)))?;
+
+        let temp_path = format!("{}.tmp", self.index_path);
+
+        index
+            .save(&temp_path)
+            .map_err(|e| Error::from_reason(format!("Failed to save index: {:?}", e)))?;
+
+        std::fs::rename(&temp_path, &self.index_path)
+            .map_err(|e| Error::from_reason(format!("Failed to rename index file: {}", e)))?;
+
+        VexusIndex::write_meta(&self.index_path, self.metric, self.quantization)?;
+
+        Ok(())
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
 pub struct RecoverTask {
     index: Arc<RwLock<Index>>,
     db_path: String,